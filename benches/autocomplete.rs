@@ -0,0 +1,108 @@
+//! Measures suggestion times of an [AutocompleteIndex] with a PTR sized tag
+//! count. Run with `cargo bench --bench autocomplete`.
+
+use hydrus_ptr_client::{
+    AutocompleteIndex, ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse,
+};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const TAG_COUNT: u64 = 1_000_000;
+const MAPPING_COUNT: u64 = 5_000_000;
+const NAMESPACES: [&str; 4] = ["", "character:", "series:", "creator:"];
+const MAX_SUGGESTION_TIME: Duration = Duration::from_millis(10);
+
+/// A xorshift generator so that every run indexes the same tags
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn word(&mut self) -> String {
+        let len = 3 + self.next() % 10;
+
+        (0..len)
+            .map(|_| char::from(b'a' + (self.next() % 26) as u8))
+            .collect()
+    }
+}
+
+fn build_index() -> AutocompleteIndex {
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    let mut index = AutocompleteIndex::new();
+    let tags = (0..TAG_COUNT)
+        .map(|id| {
+            let namespace = NAMESPACES[(random.next() % 4) as usize];
+            (id, format!("{namespace}{}", random.word()))
+        })
+        .collect();
+    index.apply_definitions(&DefinitionsUpdateResponse {
+        hashes: HashMap::new(),
+        tags,
+    });
+
+    let mut mappings = HashMap::<u64, Vec<u64>>::new();
+    for _ in 0..MAPPING_COUNT {
+        // squaring skews the distribution so that few tags have most mappings
+        let tag_id = (random.next() % 1000).pow(2) * TAG_COUNT / 1_000_000;
+        mappings
+            .entry(tag_id + random.next() % 1000)
+            .or_default()
+            .push(random.next() % 1_000_000);
+    }
+    index.apply_content(&ContentUpdateResponse {
+        mappings: HashMap::from([(ContentUpdateAction::Add, mappings)]),
+        tag_siblings: HashMap::new(),
+        tag_parents: HashMap::new(),
+    });
+
+    index
+}
+
+fn measure(index: &AutocompleteIndex, inputs: &[String]) -> Duration {
+    let mut slowest = Duration::ZERO;
+
+    for input in inputs {
+        let start = Instant::now();
+        let suggestions = index.suggest(input, 20);
+        let elapsed = start.elapsed();
+        std::hint::black_box(suggestions);
+        slowest = slowest.max(elapsed);
+    }
+
+    slowest
+}
+
+fn main() {
+    let start = Instant::now();
+    let index = build_index();
+    println!("indexed {} tags in {:?}", index.len(), start.elapsed());
+
+    let letters = ('a'..='z').map(String::from).collect::<Vec<_>>();
+    let mut random = Random(0x9e37_79b9_7f4a_7c15);
+    let words = (0..1000)
+        .map(|_| random.word()[..3].to_string())
+        .collect::<Vec<_>>();
+    let namespaced = letters
+        .iter()
+        .map(|letter| format!("character:{letter}"))
+        .collect::<Vec<_>>();
+
+    for (name, inputs) in [
+        ("one character prefixes", &letters),
+        ("three character prefixes", &words),
+        ("namespaced prefixes", &namespaced),
+    ] {
+        let slowest = measure(&index, inputs);
+        println!("{name}: slowest suggestion took {slowest:?}");
+        assert!(
+            slowest < MAX_SUGGESTION_TIME,
+            "{name} took longer than {MAX_SUGGESTION_TIME:?}"
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use crate::tag_siblings::TagSiblings;
+use crate::{
+    ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse, UpdateResponse,
+};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// Inputs with up to this many characters are answered from precomputed rankings
+/// because their prefix matches too many tags to scan them
+const RANKED_PREFIX_LEN: usize = 2;
+
+/// The namespace filter and subtag prefix of a ranking
+type RankingKey = (Option<String>, String);
+
+/// A displayed tag ordered by its count and name
+type RankingEntry = (Reverse<u64>, Arc<str>, u64);
+
+/// A tag suggested by an [AutocompleteIndex] with the number of files it's mapped to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagSuggestion<'a> {
+    pub tag: &'a str,
+    pub count: u64,
+}
+
+/// Suggests tags by the prefix of their subtag similar to the hydrus client.
+///
+/// Tags are suggested as their ideal sibling and a tag matches if the subtag
+/// of any of its siblings starts with the input. Counts are the number of
+/// distinct files mapped to any tag of a sibling group. The index keeps the
+/// mapped file ids of every tag to count them, which takes several gigabytes
+/// for the whole PTR.
+#[derive(Clone, Debug, Default)]
+pub struct AutocompleteIndex {
+    tags: HashMap<u64, Arc<str>>,
+    subtags: BTreeSet<(String, u64)>,
+    files_by_tag: HashMap<u64, HashSet<u64>>,
+    siblings: TagSiblings,
+    /// The number of files displayed with an ideal tag
+    display_counts: HashMap<u64, u64>,
+    /// The entry and keys every suggested ideal tag is ranked with
+    ranked_tags: HashMap<u64, (RankingEntry, Vec<RankingKey>)>,
+    rankings: HashMap<RankingKey, BTreeSet<RankingEntry>>,
+}
+
+impl AutocompleteIndex {
+    /// Creates a new empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a definitions or content update
+    pub fn apply(&mut self, update: &UpdateResponse) {
+        match update {
+            UpdateResponse::Definitions(definitions) => self.apply_definitions(definitions),
+            UpdateResponse::Content(content) => self.apply_content(content),
+        }
+    }
+
+    /// Adds the tags defined in the given update to the index
+    pub fn apply_definitions(&mut self, update: &DefinitionsUpdateResponse) {
+        for (id, tag) in &update.tags {
+            if let Some(previous) = self.tags.insert(*id, Arc::from(tag.as_str())) {
+                self.subtags.remove(&(subtag_key(&previous), *id));
+            }
+            self.subtags.insert((subtag_key(tag), *id));
+            self.rank(self.siblings.ideal(*id), true);
+        }
+    }
+
+    /// Updates the mappings and siblings with the given update.
+    /// Additions are applied before deletions.
+    #[tracing::instrument(skip_all, level = "trace")]
+    pub fn apply_content(&mut self, update: &ContentUpdateResponse) {
+        let mut recounted_ids = HashSet::new();
+        let mut regrouped_ids = HashSet::new();
+
+        for action in [ContentUpdateAction::Add, ContentUpdateAction::Delete] {
+            for (tag_id, hash_ids) in update.mappings.get(&action).into_iter().flatten() {
+                for hash_id in hash_ids {
+                    recounted_ids.extend(self.change_mapping(&action, *tag_id, *hash_id));
+                }
+            }
+            for (tag_id, sibling_id) in update.tag_siblings.get(&action).into_iter().flatten() {
+                regrouped_ids.extend(self.change_sibling(&action, *tag_id, *sibling_id));
+            }
+        }
+        for ideal_id in recounted_ids.difference(&regrouped_ids) {
+            self.rank(*ideal_id, false);
+        }
+        for ideal_id in regrouped_ids {
+            self.rank(ideal_id, true);
+        }
+    }
+
+    /// Returns the number of tags in the index
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Returns if the index doesn't contain any tags
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Returns the number of files the given tag or any of its siblings is mapped to
+    pub fn count(&self, tag: &str) -> u64 {
+        let key = subtag_key(tag);
+
+        self.subtags
+            .range((key.clone(), 0)..=(key, u64::MAX))
+            .find(|(_, id)| self.tags.get(id).map(AsRef::as_ref) == Some(tag))
+            .and_then(|(_, id)| self.display_counts.get(&self.siblings.ideal(*id)))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns up to `limit` tags whose subtag starts with the given input
+    /// ordered by their count. An input like `character:sa` only suggests
+    /// tags of the `character` namespace.
+    #[tracing::instrument(skip(self), level = "trace")]
+    pub fn suggest(&self, input: &str, limit: usize) -> Vec<TagSuggestion<'_>> {
+        let input = input.trim().to_lowercase();
+        let (namespace, prefix) = match input.split_once(':') {
+            Some((namespace, subtag)) => (Some(namespace), subtag),
+            None => (None, input.as_str()),
+        };
+
+        if prefix.chars().count() <= RANKED_PREFIX_LEN {
+            let key = (namespace.map(String::from), prefix.to_string());
+
+            return self
+                .rankings
+                .get(&key)
+                .into_iter()
+                .flatten()
+                .take(limit)
+                .map(|(Reverse(count), tag, _)| TagSuggestion { tag, count: *count })
+                .collect();
+        }
+        let ideal_ids = self
+            .subtags
+            .range((prefix.to_string(), 0)..)
+            .take_while(|(subtag, _)| subtag.starts_with(prefix))
+            .map(|(_, id)| self.siblings.ideal(*id))
+            .collect::<HashSet<_>>();
+        let mut best = BinaryHeap::new();
+
+        for ideal_id in ideal_ids {
+            let Some(((Reverse(count), tag, _), _)) = self.ranked_tags.get(&ideal_id) else {
+                continue;
+            };
+            if namespace.is_some_and(|n| namespace_key(tag) != n) {
+                continue;
+            }
+            best.push(Reverse((*count, Reverse(tag.as_ref()))));
+            if best.len() > limit {
+                best.pop();
+            }
+        }
+
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((count, Reverse(tag)))| TagSuggestion { tag, count })
+            .collect()
+    }
+
+    /// Adds or deletes a mapping and returns the ideal tag whose count changed
+    fn change_mapping(
+        &mut self,
+        action: &ContentUpdateAction,
+        tag_id: u64,
+        hash_id: u64,
+    ) -> Option<u64> {
+        let changed = match action {
+            ContentUpdateAction::Add => {
+                self.files_by_tag.entry(tag_id).or_default().insert(hash_id)
+            }
+            ContentUpdateAction::Delete => match self.files_by_tag.get_mut(&tag_id) {
+                Some(hash_ids) => {
+                    let removed = hash_ids.remove(&hash_id);
+                    if hash_ids.is_empty() {
+                        self.files_by_tag.remove(&tag_id);
+                    }
+                    removed
+                }
+                None => false,
+            },
+        };
+        if !changed {
+            return None;
+        }
+        let ideal_id = self.siblings.ideal(tag_id);
+        let mapped_by_sibling = self.siblings.group(ideal_id).into_iter().any(|id| {
+            id != tag_id
+                && self
+                    .files_by_tag
+                    .get(&id)
+                    .is_some_and(|hash_ids| hash_ids.contains(&hash_id))
+        });
+        if mapped_by_sibling {
+            return None;
+        }
+        let count = self.display_counts.entry(ideal_id).or_default();
+
+        match action {
+            ContentUpdateAction::Add => *count += 1,
+            ContentUpdateAction::Delete => *count -= 1,
+        }
+        if *count == 0 {
+            self.display_counts.remove(&ideal_id);
+        }
+
+        Some(ideal_id)
+    }
+
+    /// Adds or deletes a sibling and returns the ideal tags whose siblings changed
+    fn change_sibling(
+        &mut self,
+        action: &ContentUpdateAction,
+        tag_id: u64,
+        sibling_id: u64,
+    ) -> Vec<u64> {
+        let previous_ideal_id = self.siblings.ideal(tag_id);
+        self.siblings.change(action, tag_id, sibling_id);
+        let ideal_id = self.siblings.ideal(tag_id);
+
+        if ideal_id == previous_ideal_id {
+            return Vec::new();
+        }
+        self.recount(previous_ideal_id);
+        self.recount(ideal_id);
+
+        vec![previous_ideal_id, ideal_id]
+    }
+
+    /// Counts the distinct files of all tags displayed as the given ideal tag
+    fn recount(&mut self, ideal_id: u64) {
+        let group = self.siblings.group(ideal_id);
+        let count = match group.as_slice() {
+            [id] => self.files_by_tag.get(id).map_or(0, HashSet::len),
+            ids => ids
+                .iter()
+                .filter_map(|id| self.files_by_tag.get(id))
+                .flatten()
+                .collect::<HashSet<_>>()
+                .len(),
+        } as u64;
+
+        if count == 0 {
+            self.display_counts.remove(&ideal_id);
+        } else {
+            self.display_counts.insert(ideal_id, count);
+        }
+    }
+
+    /// Moves the ideal tag to its current count in the rankings. The ranking
+    /// keys are only computed again if the tag or its siblings changed.
+    fn rank(&mut self, ideal_id: u64, regroup: bool) {
+        let previous = self.ranked_tags.remove(&ideal_id);
+
+        if let Some((entry, keys)) = &previous {
+            for key in keys {
+                if let Some(ranking) = self.rankings.get_mut(key) {
+                    ranking.remove(entry);
+                    if ranking.is_empty() {
+                        self.rankings.remove(key);
+                    }
+                }
+            }
+        }
+        let count = self
+            .display_counts
+            .get(&ideal_id)
+            .copied()
+            .unwrap_or_default();
+        let Some(tag) = self.tags.get(&ideal_id).filter(|_| count > 0) else {
+            return;
+        };
+        let entry = (Reverse(count), tag.clone(), ideal_id);
+        let keys = match previous {
+            Some((_, keys)) if !regroup => keys,
+            _ => self.ranking_keys(ideal_id, tag),
+        };
+
+        for key in &keys {
+            self.rankings
+                .entry(key.clone())
+                .or_default()
+                .insert(entry.clone());
+        }
+        self.ranked_tags.insert(ideal_id, (entry, keys));
+    }
+
+    /// Returns the short prefixes of all siblings of the ideal tag
+    /// with and without the namespace of the ideal tag
+    fn ranking_keys(&self, ideal_id: u64, tag: &str) -> Vec<RankingKey> {
+        let namespace = namespace_key(tag);
+        let prefixes = self
+            .siblings
+            .group(ideal_id)
+            .into_iter()
+            .filter_map(|id| self.tags.get(&id))
+            .flat_map(|tag| {
+                let subtag = subtag_key(tag);
+                let lengths = subtag
+                    .char_indices()
+                    .take(RANKED_PREFIX_LEN)
+                    .map(|(i, c)| i + c.len_utf8());
+
+                [0].into_iter()
+                    .chain(lengths)
+                    .map(|len| subtag[..len].to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<BTreeSet<_>>();
+
+        prefixes
+            .into_iter()
+            .flat_map(|prefix| [(None, prefix.clone()), (Some(namespace.clone()), prefix)])
+            .collect()
+    }
+}
+
+/// Returns the lowercase subtag of the tag that is used for prefix lookups
+fn subtag_key(tag: &str) -> String {
+    tag.split_once(':')
+        .map(|(_, subtag)| subtag)
+        .unwrap_or(tag)
+        .to_lowercase()
+}
+
+/// Returns the lowercase namespace of the tag which is empty for unnamespaced tags
+fn namespace_key(tag: &str) -> String {
+    tag.split_once(':')
+        .map(|(namespace, _)| namespace.to_lowercase())
+        .unwrap_or_default()
+}
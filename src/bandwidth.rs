@@ -0,0 +1,306 @@
+use crate::hydrus_serializable::bandwidth_rules::HydrusBandwidthRules;
+use crate::{Error, Result};
+use std::collections::VecDeque;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+const BANDWIDTH_TYPE_DATA: u8 = 0;
+const BANDWIDTH_TYPE_REQUESTS: u8 = 1;
+const MIN_TRACKING_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The kind of bandwidth a rule limits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BandwidthType {
+    /// The number of received bytes
+    Data,
+    /// The number of requests
+    Requests,
+}
+
+/// Limits the usage of one bandwidth type within a time window.
+/// A rule without a time window limits the total usage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BandwidthRule {
+    pub bandwidth_type: BandwidthType,
+    pub time_delta: Option<Duration>,
+    pub max_allowed: u64,
+}
+
+/// A set of bandwidth rules that all have to be satisfied
+/// before a request is made
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BandwidthRules {
+    rules: Vec<BandwidthRule>,
+}
+
+impl BandwidthRules {
+    /// Creates a new empty set of rules that allows everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule allowing at most `max_allowed` bytes or requests
+    /// within the given time window
+    pub fn add_rule(
+        mut self,
+        bandwidth_type: BandwidthType,
+        time_delta: Option<Duration>,
+        max_allowed: u64,
+    ) -> Self {
+        self.rules.push(BandwidthRule {
+            bandwidth_type,
+            time_delta,
+            max_allowed,
+        });
+
+        self
+    }
+
+    /// Returns all rules
+    pub fn rules(&self) -> &[BandwidthRule] {
+        &self.rules
+    }
+
+    /// Returns if there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub(crate) fn from_hydrus(rules: HydrusBandwidthRules) -> Self {
+        let rules = rules
+            .0
+            .into_iter()
+            .filter_map(|(bandwidth_type, time_delta, max_allowed)| {
+                let bandwidth_type = match bandwidth_type {
+                    BANDWIDTH_TYPE_DATA => BandwidthType::Data,
+                    BANDWIDTH_TYPE_REQUESTS => BandwidthType::Requests,
+                    other => {
+                        tracing::warn!("ignoring rule with unknown bandwidth type {}", other);
+                        return None;
+                    }
+                };
+                Some(BandwidthRule {
+                    bandwidth_type,
+                    time_delta: time_delta.map(Duration::from_secs),
+                    max_allowed,
+                })
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    pub(crate) fn to_hydrus(&self) -> HydrusBandwidthRules {
+        HydrusBandwidthRules(
+            self.rules
+                .iter()
+                .map(|rule| {
+                    let bandwidth_type = match rule.bandwidth_type {
+                        BandwidthType::Data => BANDWIDTH_TYPE_DATA,
+                        BandwidthType::Requests => BANDWIDTH_TYPE_REQUESTS,
+                    };
+                    (
+                        bandwidth_type,
+                        rule.time_delta.map(|d| d.as_secs()),
+                        rule.max_allowed,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the longest time window of all rules
+    fn longest_time_delta(&self) -> Option<Duration> {
+        self.rules.iter().filter_map(|r| r.time_delta).max()
+    }
+}
+
+/// Counts the used bytes and requests over time
+#[derive(Clone, Debug, Default)]
+pub struct BandwidthTracker {
+    events: VecDeque<BandwidthEvent>,
+    total_bytes: u64,
+    total_requests: u64,
+}
+
+#[derive(Clone, Debug)]
+struct BandwidthEvent {
+    time: Instant,
+    bytes: u64,
+    requests: u64,
+}
+
+impl BandwidthEvent {
+    fn value(&self, bandwidth_type: BandwidthType) -> u64 {
+        match bandwidth_type {
+            BandwidthType::Data => self.bytes,
+            BandwidthType::Requests => self.requests,
+        }
+    }
+}
+
+impl BandwidthTracker {
+    /// Creates a new tracker without any usage
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports a request that was made
+    pub fn report_request(&mut self) {
+        self.report(Instant::now(), 0, 1);
+    }
+
+    /// Reports the given number of received bytes
+    pub fn report_data(&mut self, bytes: u64) {
+        self.report(Instant::now(), bytes, 0);
+    }
+
+    /// Returns the usage within the given time window
+    /// or the total usage if no window is given
+    pub fn usage(&self, bandwidth_type: BandwidthType, time_delta: Option<Duration>) -> u64 {
+        self.usage_at(Instant::now(), bandwidth_type, time_delta)
+    }
+
+    /// Returns how long to wait until all rules allow another request.
+    /// Fails if a rule without a time window has been exceeded
+    /// as waiting won't help in that case.
+    pub fn wait_time(&self, rules: &BandwidthRules) -> Result<Duration> {
+        self.wait_time_at(Instant::now(), rules)
+    }
+
+    /// Forgets about usage that is older than the given duration.
+    /// The total usage is kept.
+    pub fn prune(&mut self, max_age: Duration) {
+        if let Some(cutoff) = Instant::now().checked_sub(max_age) {
+            while self.events.front().map(|e| e.time < cutoff) == Some(true) {
+                self.events.pop_front();
+            }
+        }
+    }
+
+    fn report(&mut self, time: Instant, bytes: u64, requests: u64) {
+        self.total_bytes += bytes;
+        self.total_requests += requests;
+        self.events.push_back(BandwidthEvent {
+            time,
+            bytes,
+            requests,
+        });
+    }
+
+    fn usage_at(
+        &self,
+        now: Instant,
+        bandwidth_type: BandwidthType,
+        time_delta: Option<Duration>,
+    ) -> u64 {
+        match time_delta {
+            None => match bandwidth_type {
+                BandwidthType::Data => self.total_bytes,
+                BandwidthType::Requests => self.total_requests,
+            },
+            Some(delta) => self
+                .events_within(now, delta)
+                .map(|e| e.value(bandwidth_type))
+                .sum(),
+        }
+    }
+
+    fn wait_time_at(&self, now: Instant, rules: &BandwidthRules) -> Result<Duration> {
+        let mut wait_time = Duration::ZERO;
+
+        for rule in &rules.rules {
+            let mut usage = self.usage_at(now, rule.bandwidth_type, rule.time_delta);
+
+            if usage < rule.max_allowed {
+                continue;
+            }
+            let delta = match rule.time_delta {
+                Some(delta) if rule.max_allowed > 0 => delta,
+                _ => return Err(Error::BandwidthExceeded(format!("{:?}", rule))),
+            };
+            for event in self.events_within(now, delta) {
+                usage -= event.value(rule.bandwidth_type);
+
+                if usage < rule.max_allowed {
+                    wait_time = wait_time.max((event.time + delta).saturating_duration_since(now));
+                    break;
+                }
+            }
+        }
+
+        Ok(wait_time)
+    }
+
+    fn events_within(
+        &self,
+        now: Instant,
+        delta: Duration,
+    ) -> impl Iterator<Item = &BandwidthEvent> {
+        let cutoff = now.checked_sub(delta);
+
+        self.events
+            .iter()
+            .filter(move |e| cutoff.map(|c| e.time > c).unwrap_or(true))
+    }
+}
+
+/// Bandwidth rules and usage shared between all clones of a client
+#[derive(Debug, Default)]
+pub(crate) struct BandwidthLimiter {
+    rules: RwLock<BandwidthRules>,
+    tracker: Mutex<BandwidthTracker>,
+}
+
+impl BandwidthLimiter {
+    pub fn new(rules: BandwidthRules) -> Self {
+        Self {
+            rules: RwLock::new(rules),
+            tracker: Mutex::default(),
+        }
+    }
+
+    pub fn rules(&self) -> BandwidthRules {
+        self.rules.read().unwrap().clone()
+    }
+
+    pub fn set_rules(&self, rules: BandwidthRules) {
+        *self.rules.write().unwrap() = rules;
+    }
+
+    pub fn usage(&self, bandwidth_type: BandwidthType, time_delta: Option<Duration>) -> u64 {
+        self.tracker
+            .lock()
+            .unwrap()
+            .usage(bandwidth_type, time_delta)
+    }
+
+    /// Waits until the rules allow another request and reports it
+    pub async fn acquire(&self) -> Result<()> {
+        loop {
+            let wait_time = {
+                let rules = self.rules.read().unwrap();
+                let mut tracker = self.tracker.lock().unwrap();
+                let wait_time = tracker.wait_time(&rules)?;
+
+                if wait_time.is_zero() {
+                    tracker.report_request();
+                    tracker.prune(
+                        rules
+                            .longest_time_delta()
+                            .unwrap_or_default()
+                            .max(MIN_TRACKING_PERIOD),
+                    );
+                    return Ok(());
+                }
+                wait_time
+            };
+            tracing::debug!("bandwidth limit reached, waiting {:?}", wait_time);
+            tokio::time::sleep(wait_time).await;
+        }
+    }
+
+    pub fn report_data(&self, bytes: u64) {
+        self.tracker.lock().unwrap().report_data(bytes);
+    }
+}
@@ -0,0 +1,72 @@
+use hydrus_ptr_client::{Client, Mirror, PtrStorage, ServerTls};
+use std::env;
+use std::net::SocketAddr;
+use std::process::exit;
+use std::sync::Arc;
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:45871";
+
+/// Syncs the PTR configured with the `PTR_URL` and `PTR_ACCESS_KEY` environment variables
+/// into the given storage directory and serves it on the given address.
+/// The PEM encoded certificate and private key used for https are read from the files
+/// in `PTR_MIRROR_CERT` and `PTR_MIRROR_KEY`. Plain http is only served if
+/// `PTR_MIRROR_PLAIN_HTTP` is set as hydrus clients only connect over https.
+///
+/// Usage: `ptr-mirror <storage-dir> [address]`
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+    let mut args = env::args().skip(1);
+
+    let storage_dir = args.next().unwrap_or_else(|| {
+        eprintln!("Usage: ptr-mirror <storage-dir> [address]");
+        exit(1)
+    });
+    let address = args
+        .next()
+        .unwrap_or_else(|| String::from(DEFAULT_ADDRESS))
+        .parse::<SocketAddr>()
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid address: {}", e);
+            exit(1)
+        });
+
+    let mut builder = Client::builder();
+    if let Ok(url) = env::var("PTR_URL") {
+        builder = builder.endpoint(url);
+    }
+    if let Ok(key) = env::var("PTR_ACCESS_KEY") {
+        builder = builder.access_key(key);
+    }
+    if env::var("PTR_ACCEPT_INVALID_CERTS").is_ok() {
+        builder = builder.accept_invalid_certs(true);
+    }
+    let tls = match (env::var("PTR_MIRROR_CERT"), env::var("PTR_MIRROR_KEY")) {
+        (Ok(certificate), Ok(key)) => Some(
+            ServerTls::from_pem_files(certificate, key).unwrap_or_else(|e| {
+                eprintln!("Invalid certificate or key: {}", e);
+                exit(1)
+            }),
+        ),
+        _ if env::var("PTR_MIRROR_PLAIN_HTTP").is_ok() => None,
+        _ => {
+            eprintln!("PTR_MIRROR_CERT and PTR_MIRROR_KEY are required to serve https");
+            exit(1)
+        }
+    };
+    let client = builder.build().expect("failed to build the client");
+    let storage = PtrStorage::open(storage_dir).expect("failed to open the storage");
+    let mirror = Arc::new(Mirror::new(client, storage).expect("failed to create the mirror"));
+
+    let sync_mirror = Arc::clone(&mirror);
+    tokio::spawn(async move { sync_mirror.sync_forever().await });
+
+    let result = match tls {
+        Some(tls) => mirror.serve_tls(address, tls).await,
+        None => mirror.serve(address).await,
+    };
+    if let Err(e) = result {
+        eprintln!("Server failed: {}", e);
+        exit(1);
+    }
+}
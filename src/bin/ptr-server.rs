@@ -0,0 +1,119 @@
+use hydrus_ptr_client::{OptionsResponse, PtrStorage, RepositoryServer, ServerTls};
+use std::env;
+use std::net::SocketAddr;
+use std::process::exit;
+use std::sync::Arc;
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:45871";
+const DEFAULT_UPDATE_PERIOD: u64 = 100_000;
+const USAGE: &str = "Usage: ptr-server [--allow-anonymous-writes] (--cert <file> --key <file> | --plain-http) <storage-dir> [address]";
+
+/// Runs a tag repository storing its data in the given storage directory.
+/// Access keys are read from the comma separated `PTR_SERVER_ACCESS_KEYS`
+/// environment variable, the update period in seconds from `PTR_UPDATE_PERIOD`
+/// and the server message from `PTR_SERVER_MESSAGE`. The server refuses to start
+/// without access keys unless `--allow-anonymous-writes` is passed.
+/// The repository is served over https with the PEM certificate chain and private
+/// key passed with `--cert` and `--key`. Plain http is only served with `--plain-http`
+/// as hydrus clients only connect over https.
+///
+/// Usage: `ptr-server [--allow-anonymous-writes] (--cert <file> --key <file> | --plain-http) <storage-dir> [address]`
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+    let mut allow_anonymous_writes = false;
+    let mut plain_http = false;
+    let mut certificate = None;
+    let mut key = None;
+    let mut args = Vec::new();
+    let mut arg_iter = env::args().skip(1);
+
+    while let Some(arg) = arg_iter.next() {
+        match arg.as_str() {
+            "--allow-anonymous-writes" => allow_anonymous_writes = true,
+            "--plain-http" => plain_http = true,
+            "--cert" => certificate = Some(option_value(&mut arg_iter, &arg)),
+            "--key" => key = Some(option_value(&mut arg_iter, &arg)),
+            flag if flag.starts_with("--") => {
+                eprintln!("Unknown option {}", flag);
+                exit(1);
+            }
+            _ => args.push(arg),
+        }
+    }
+    let tls = match (certificate, key) {
+        (Some(certificate), Some(key)) => Some(
+            ServerTls::from_pem_files(certificate, key).unwrap_or_else(|e| {
+                eprintln!("Invalid certificate or key: {}", e);
+                exit(1)
+            }),
+        ),
+        (None, None) if plain_http => None,
+        _ => {
+            eprintln!("--cert and --key are required to serve https");
+            eprintln!("{}", USAGE);
+            exit(1)
+        }
+    };
+    let mut args = args.into_iter();
+
+    let storage_dir = args.next().unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        exit(1)
+    });
+    let address = args
+        .next()
+        .unwrap_or_else(|| String::from(DEFAULT_ADDRESS))
+        .parse::<SocketAddr>()
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid address: {}", e);
+            exit(1)
+        });
+    let update_period = env::var("PTR_UPDATE_PERIOD")
+        .ok()
+        .map(|p| {
+            p.parse::<u64>().unwrap_or_else(|e| {
+                eprintln!("Invalid update period: {}", e);
+                exit(1)
+            })
+        })
+        .unwrap_or(DEFAULT_UPDATE_PERIOD);
+    let options = OptionsResponse {
+        server_message: env::var("PTR_SERVER_MESSAGE").unwrap_or_default(),
+        update_period,
+        nullification_period: 0,
+        tag_filter: serde_json::json!([]),
+    };
+
+    let storage = PtrStorage::open(storage_dir).expect("failed to open the storage");
+    let mut server = RepositoryServer::open(storage, options)
+        .await
+        .expect("failed to open the repository")
+        .allow_anonymous_writes(allow_anonymous_writes);
+    if let Ok(keys) = env::var("PTR_SERVER_ACCESS_KEYS") {
+        for key in keys.split(',').filter(|k| !k.is_empty()) {
+            server = server.access_key(key);
+        }
+    }
+    let server = Arc::new(server);
+
+    let publish_server = Arc::clone(&server);
+    tokio::spawn(async move { publish_server.publish_forever().await });
+
+    let result = match tls {
+        Some(tls) => server.serve_tls(address, tls).await,
+        None => server.serve(address).await,
+    };
+    if let Err(e) = result {
+        eprintln!("Server failed: {}", e);
+        exit(1);
+    }
+}
+
+/// Returns the value following an option or exits if it is missing
+fn option_value(args: &mut impl Iterator<Item = String>, option: &str) -> String {
+    args.next().unwrap_or_else(|| {
+        eprintln!("Missing value for {}", option);
+        exit(1)
+    })
+}
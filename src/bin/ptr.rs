@@ -0,0 +1,384 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use futures_util::StreamExt;
+use hydrus_ptr_client::{
+    Client, MetadataResponse, PtrStorage, ReportCollector, Sha256Hash, StreamOrdering, TagDisplay,
+    TagIndex, UpdateFilter, UpdateReport, UpdateStream,
+};
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::exit;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Inspects and syncs the hydrus PTR
+#[derive(Parser)]
+#[command(name = "ptr", version)]
+struct Args {
+    /// The endpoint of the PTR
+    #[arg(long, global = true, env = "PTR_URL")]
+    endpoint: Option<String>,
+
+    /// The access key used for requests to the PTR
+    #[arg(long, global = true, env = "PTR_ACCESS_KEY", hide_env_values = true)]
+    access_key: Option<String>,
+
+    /// Doesn't validate the certificate of the PTR
+    #[arg(long, global = true)]
+    accept_invalid_certs: bool,
+
+    /// A PEM encoded root certificate to trust in addition to the system ones
+    #[arg(long, global = true, env = "PTR_ROOT_CERT")]
+    root_cert: Option<PathBuf>,
+
+    /// The SHA-256 fingerprint of the only certificate accepted from the PTR
+    #[cfg(feature = "rustls")]
+    #[arg(long, global = true, env = "PTR_PINNED_CERT")]
+    pinned_cert: Option<String>,
+
+    /// The http, https or socks5 proxy used for requests
+    #[arg(long, global = true, env = "PTR_PROXY")]
+    proxy: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints the options of the PTR
+    Options {
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+
+    /// Prints the available updates since the given update index
+    Metadata {
+        #[arg(long, default_value_t = 0)]
+        since: u64,
+
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+
+    /// Prints the update file with the given hash
+    Update {
+        hash: Sha256Hash,
+
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+
+    /// Downloads all update files that aren't stored in the given directory yet
+    Sync {
+        #[arg(long)]
+        db: PathBuf,
+    },
+
+    /// Prints the tags of the file with the given hash from the synced updates
+    Lookup {
+        file_hash: Sha256Hash,
+
+        #[arg(long)]
+        db: PathBuf,
+
+        /// Applies tag siblings and parents to the printed tags
+        #[arg(long, value_enum, default_value_t = Display::Storage)]
+        display: Display,
+    },
+
+    /// Prints a report of the changes made by the synced updates
+    Report {
+        #[arg(long)]
+        db: PathBuf,
+
+        /// Only reports updates with this or a later update index
+        #[arg(long, default_value_t = 0)]
+        since: u64,
+
+        /// Only reports updates of the given number of past days
+        #[arg(long)]
+        days: Option<u64>,
+
+        /// The number of most active tags to print
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Display {
+    Storage,
+    Siblings,
+    Parents,
+}
+
+impl From<Display> for TagDisplay {
+    fn from(display: Display) -> Self {
+        match display {
+            Display::Storage => TagDisplay::Storage,
+            Display::Siblings => TagDisplay::Siblings,
+            Display::Parents => TagDisplay::SiblingsAndParents,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+    let args = Args::parse();
+
+    if let Err(e) = run(args).await {
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<()> {
+    let client = build_client(&args)?;
+
+    match args.command {
+        Command::Options { format } => print_options(&client, format).await,
+        Command::Metadata { since, format } => print_metadata(&client, since, format).await,
+        Command::Update { hash, format } => print_update(&client, &hash, format).await,
+        Command::Sync { db } => sync(&client, db).await,
+        Command::Lookup {
+            file_hash,
+            db,
+            display,
+        } => lookup(&file_hash, db, display.into()).await,
+        Command::Report {
+            db,
+            since,
+            days,
+            top,
+            format,
+        } => report(db, since, days, top, format).await,
+    }
+}
+
+fn build_client(args: &Args) -> Result<Client> {
+    let mut builder = Client::builder().accept_invalid_certs(args.accept_invalid_certs);
+
+    if let Some(endpoint) = &args.endpoint {
+        builder = builder.endpoint(endpoint);
+    }
+    if let Some(access_key) = &args.access_key {
+        builder = builder.access_key(access_key);
+    }
+    if let Some(path) = &args.root_cert {
+        builder =
+            builder.add_root_certificate(reqwest::Certificate::from_pem(&std::fs::read(path)?)?);
+    }
+    #[cfg(feature = "rustls")]
+    if let Some(fingerprint) = &args.pinned_cert {
+        builder = builder.pinned_certificate(Sha256Hash::from_hex(fingerprint.replace(':', ""))?);
+    }
+    if let Some(proxy) = &args.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+
+    Ok(builder.build()?)
+}
+
+async fn print_options(client: &Client, format: Format) -> Result<()> {
+    let options = client.get_options().await?;
+
+    match format {
+        Format::Text => {
+            println!("server message: {}", options.server_message);
+            println!("update period: {}s", options.update_period);
+            println!("nullification period: {}s", options.nullification_period);
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&options)?),
+    }
+
+    Ok(())
+}
+
+async fn print_metadata(client: &Client, since: u64, format: Format) -> Result<()> {
+    let metadata = client.get_metadata(since).await?;
+
+    match format {
+        Format::Text => {
+            for entry in &metadata.0.entries {
+                for hash in &entry.update_hashes {
+                    println!(
+                        "{}\t{}\t{}\t{}",
+                        entry.update_index, entry.time_begin, entry.time_end, hash
+                    );
+                }
+            }
+            println!("next update due: {}", metadata.0.next_update_due);
+        }
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&metadata_json(&metadata))?
+        ),
+    }
+
+    Ok(())
+}
+
+fn metadata_json(metadata: &MetadataResponse) -> serde_json::Value {
+    let entries = metadata
+        .0
+        .entries
+        .iter()
+        .map(|e| {
+            json!({
+                "update_index": e.update_index,
+                "update_hashes": e.update_hashes,
+                "time_begin": e.time_begin,
+                "time_end": e.time_end,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "entries": entries,
+        "next_update_due": metadata.0.next_update_due,
+    })
+}
+
+async fn print_update(client: &Client, hash: &Sha256Hash, format: Format) -> Result<()> {
+    let update = client.get_update(hash).await?;
+
+    match format {
+        Format::Text => println!("{:#?}", update),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&update)?),
+    }
+
+    Ok(())
+}
+
+async fn sync(client: &Client, db: PathBuf) -> Result<()> {
+    let storage = PtrStorage::open(db)?;
+    let summary = storage.sync(client).await?;
+    println!(
+        "downloaded {} new of {} total updates",
+        summary.downloaded_updates, summary.total_updates
+    );
+
+    Ok(())
+}
+
+async fn lookup(file_hash: &Sha256Hash, db: PathBuf, display: TagDisplay) -> Result<()> {
+    let storage = PtrStorage::open(db)?;
+    let metadata = storage
+        .metadata()?
+        .ok_or("no synced metadata found, run the sync command first")?;
+    let mut stream = UpdateStream::from_entries(storage.update_source(), metadata.0.entries)
+        .ordering(StreamOrdering::DefinitionsFirst)
+        .update_filter(UpdateFilter::new().hashes(HashSet::from([*file_hash])));
+    let mut index = TagIndex::new();
+
+    while let Some(update) = stream.next().await {
+        index.apply(&update?.update);
+    }
+    for tag in index.tags_for_hash(file_hash, display) {
+        println!("{}", tag);
+    }
+
+    Ok(())
+}
+
+async fn report(
+    db: PathBuf,
+    since: u64,
+    days: Option<u64>,
+    top: usize,
+    format: Format,
+) -> Result<()> {
+    let storage = PtrStorage::open(db)?;
+    let metadata = storage
+        .metadata()?
+        .ok_or("no synced metadata found, run the sync command first")?
+        .0;
+    let mut first_index = since;
+
+    if let Some(days) = days {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let recent = metadata.time_range(now.saturating_sub(days * 24 * 60 * 60), u64::MAX);
+        first_index = first_index.max(recent.entries.first().map_or(u64::MAX, |e| e.update_index));
+    }
+    // the definitions of earlier updates are streamed as well to resolve the tags defined in them
+    let mut stream = UpdateStream::from_entries(storage.update_source(), metadata.entries)
+        .ordering(StreamOrdering::DefinitionsFirst)
+        .skip_content_before(first_index);
+    let mut collector = ReportCollector::new();
+
+    while let Some(update) = stream.next().await {
+        collector.apply(&update?);
+    }
+    let report = collector.report(top);
+
+    match format {
+        Format::Text => print_report_text(&report),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    Ok(())
+}
+
+fn print_report_text(report: &UpdateReport) {
+    println!(
+        "updates from {} to {}",
+        report.time_begin.unwrap_or_default(),
+        report.time_end.unwrap_or_default()
+    );
+    println!("\nmost active tags:");
+    for tag in &report.tags {
+        println!(
+            "  +{}\t-{}\t{}",
+            tag.added,
+            tag.deleted,
+            tag.tag.as_deref().unwrap_or("<unknown>")
+        );
+    }
+    println!("\nnamespaces:");
+    for namespace in &report.namespaces {
+        println!(
+            "  +{}\t-{}\t{}",
+            namespace.added,
+            namespace.deleted,
+            namespace.namespace.as_deref().unwrap_or("<unknown>")
+        );
+    }
+    println!("\nchurn per update index:");
+    for churn in &report.churn {
+        println!(
+            "  {}\tmappings +{} -{}\tsiblings +{} -{}\tparents +{} -{}",
+            churn.update_index,
+            churn.mappings_added,
+            churn.mappings_deleted,
+            churn.siblings_added,
+            churn.siblings_deleted,
+            churn.parents_added,
+            churn.parents_deleted
+        );
+    }
+    println!("\nsibling and parent changes:");
+    for change in &report.relationship_changes {
+        println!(
+            "  {}\t{:?} {:?}\t{} -> {}",
+            change.update_index,
+            change.action,
+            change.kind,
+            change.tag.as_deref().unwrap_or("<unknown>"),
+            change.other.as_deref().unwrap_or("<unknown>")
+        );
+    }
+}
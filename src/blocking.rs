@@ -0,0 +1,157 @@
+//! A synchronous client for applications that can't run an async runtime.
+//!
+//! The blocking client wraps an async [crate::Client] together with its own
+//! single threaded tokio runtime. It must not be used from within an async
+//! context as blocking on the runtime would panic.
+//!
+//! ```no_run
+//! use hydrus_ptr_client::blocking::Client;
+//!
+//! let client = Client::new("https://ptr.hydrus.network:45871", "<access-key>").unwrap();
+//! let metadata = client.get_metadata(0).unwrap();
+//!
+//! for update in client.stream_updates(0).unwrap().take(10) {
+//!     println!("{:?}", update.unwrap().hash);
+//! }
+//! ```
+
+use crate::{
+    ClientToServerUpdate, MetadataResponse, OptionsResponse, Result, Sha256Hash, StreamOrdering,
+    StreamProgress, StreamedUpdate, UpdateFilter, UpdateResponse, UpdateStream,
+};
+use futures_core::Stream;
+use std::future::{poll_fn, Future};
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// A blocking PTR client
+#[derive(Clone)]
+pub struct Client {
+    inner: crate::Client,
+    runtime: Arc<Runtime>,
+}
+
+impl Client {
+    /// Creates a new blocking PTR client
+    pub fn new<S1: ToString, S2: ToString>(endpoint: S1, access_key: S2) -> Result<Self> {
+        Self::from_client(crate::Client::new(endpoint, access_key))
+    }
+
+    /// Creates a blocking client from an async client configured with the [crate::ClientBuilder].
+    /// Both share their bandwidth usage and rate limits.
+    pub fn from_client(client: crate::Client) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        Ok(Self {
+            inner: client,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Returns the async client used for the requests
+    pub fn inner(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    /// Returns the options of the PTR
+    pub fn get_options(&self) -> Result<OptionsResponse> {
+        self.block_on(self.inner.get_options())
+    }
+
+    /// Returns information about all available updates since the given ID
+    /// and when the next check for updates should be made
+    pub fn get_metadata(&self, since: u64) -> Result<MetadataResponse> {
+        self.block_on(self.inner.get_metadata(since))
+    }
+
+    /// Returns the metadata entries within the given range of update indices
+    pub fn get_metadata_range(&self, range: Range<u64>) -> Result<MetadataResponse> {
+        self.block_on(self.inner.get_metadata_range(range))
+    }
+
+    /// Returns the parsed update file identified by the given hash
+    pub fn get_update(&self, update_hash: &Sha256Hash) -> Result<UpdateResponse> {
+        self.block_on(self.inner.get_update(update_hash))
+    }
+
+    /// Uploads content changes to the repository
+    pub fn post_update(&self, update: &ClientToServerUpdate) -> Result<()> {
+        self.block_on(self.inner.post_update(update))
+    }
+
+    /// Returns an iterator over all update files since the given update index
+    pub fn stream_updates(&self, since: u64) -> Result<UpdateIterator> {
+        let stream = self.block_on(self.inner.stream_updates(since))?;
+
+        Ok(self.iter_stream(stream))
+    }
+
+    /// Turns an update stream into a blocking iterator
+    pub fn iter_stream(&self, stream: UpdateStream) -> UpdateIterator {
+        UpdateIterator {
+            stream,
+            runtime: Arc::clone(&self.runtime),
+        }
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+}
+
+/// A blocking iterator over the update files of an [UpdateStream]
+pub struct UpdateIterator {
+    stream: UpdateStream,
+    runtime: Arc<Runtime>,
+}
+
+impl UpdateIterator {
+    /// Sets the order in which update files are yielded
+    pub fn ordering(mut self, ordering: StreamOrdering) -> Self {
+        self.stream = self.stream.ordering(ordering);
+
+        self
+    }
+
+    /// Discards definitions and content that aren't kept by the given filter
+    pub fn update_filter(mut self, filter: UpdateFilter) -> Self {
+        self.stream = self.stream.update_filter(filter);
+
+        self
+    }
+
+    /// Returns the current progress of the iterator
+    pub fn progress(&self) -> StreamProgress {
+        self.stream.progress()
+    }
+
+    /// Returns a list of all failed hashes
+    pub fn failed_hashes(&self) -> &Vec<Sha256Hash> {
+        self.stream.failed_hashes()
+    }
+
+    /// Puts all failed hashes back into the queue for retry
+    pub fn retry_all(&mut self) {
+        self.stream.retry_all()
+    }
+
+    /// Returns the underlying stream
+    pub fn into_stream(self) -> UpdateStream {
+        self.stream
+    }
+}
+
+impl Iterator for UpdateIterator {
+    type Item = Result<StreamedUpdate>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let stream = &mut self.stream;
+
+        self.runtime
+            .block_on(poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)))
+    }
+}
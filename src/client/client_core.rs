@@ -1,9 +1,12 @@
-use crate::{Client, Error, FromJson, GetEndpoint};
-use flate2::write::ZlibDecoder;
-use reqwest::Response;
+use crate::encoding::{decode_body, encode_body};
+use crate::retry::retry_after;
+use crate::{Client, Error, GetEndpoint, HttpMethod, HttpRequest, PostEndpoint, ToJson};
 use serde::Serialize;
 use std::fmt::Debug;
-use std::io::Write;
+use std::future::Future;
+
+/// The status hydrus servers respond with when their bandwidth is used up
+const BANDWIDTH_EXCEEDED: u16 = 509;
 
 impl Client {
     /// Performs a get request to the given Get Endpoint
@@ -12,55 +15,133 @@ impl Client {
         &self,
         query: &Q,
     ) -> crate::Result<E::Response> {
-        tracing::trace!("GET request to {}", E::path());
-        let response = self
-            .client
-            .get(format!("{}/{}", self.base_url, E::path()))
-            .query(query)
-            .header("Hydrus-Key", self.access_key.to_string())
-            .send()
-            .await?;
-        let body = Self::get_body(response).await?;
-        let bytes = Self::decompress_body(body)?;
-        let response_type = Self::deserialize_body(bytes)?;
+        let body = self.get_raw::<E, Q>(query).await?;
+        let response_type = decode_body::<E::Response>(body)?;
         tracing::trace!("response is: {:?}", response_type);
 
         Ok(response_type)
     }
 
-    /// Returns the body from the response
-    #[tracing::instrument(level = "trace")]
-    async fn get_body(response: Response) -> crate::Result<Vec<u8>> {
-        if response.status().is_success() {
-            Ok(response.bytes().await?.to_vec())
-        } else {
-            let message = response.text().await?;
-            Err(Error::Response(message))
-        }
+    /// Performs a get request to the given Get Endpoint and returns
+    /// the raw compressed body. Failed requests are retried according
+    /// to the retry policy of the client.
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub(crate) async fn get_raw<E: GetEndpoint, Q: Serialize + Debug>(
+        &self,
+        query: &Q,
+    ) -> crate::Result<Vec<u8>> {
+        self.with_retries(|| self.get_raw_once::<E, Q>(query)).await
+    }
+
+    /// Performs a post request with the given body to the given Post Endpoint
+    /// and returns the raw compressed response body.
+    /// Post requests aren't idempotent so they are never retried.
+    #[tracing::instrument(skip(self, body), level = "debug")]
+    pub(crate) async fn post_raw<E: PostEndpoint>(
+        &self,
+        body: &E::Request,
+    ) -> crate::Result<Vec<u8>>
+    where
+        E::Request: ToJson,
+    {
+        let body = encode_body(body)?;
+
+        self.post_raw_once::<E>(body).await
     }
 
-    /// Uses zlib to decompress the body
-    #[tracing::instrument(skip(bytes), level = "trace")]
-    fn decompress_body(mut bytes: Vec<u8>) -> crate::Result<Vec<u8>> {
-        tracing::trace!("body length {}", bytes.len());
+    /// Runs the given request until it succeeds or the retry policy
+    /// doesn't allow any more attempts
+    async fn with_retries<F, Fut>(&self, mut request: F) -> crate::Result<Vec<u8>>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = crate::Result<Vec<u8>>>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            let result = request().await;
+            let delay = match &result {
+                Err(e) => self.retry_policy.retry_delay(attempt, e),
+                Ok(_) => None,
+            };
 
-        let mut buf = Vec::new();
-        let mut decoder = ZlibDecoder::new(buf);
+            match (result, delay) {
+                (Err(e), Some(delay)) => {
+                    tracing::debug!(
+                        "attempt {} failed with {}, retrying in {:?}",
+                        attempt,
+                        e,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                (result, _) => return result,
+            }
+        }
+    }
+
+    /// Performs a single get request to the given Get Endpoint
+    async fn get_raw_once<E: GetEndpoint, Q: Serialize + Debug>(
+        &self,
+        query: &Q,
+    ) -> crate::Result<Vec<u8>> {
+        tracing::trace!("GET request to {}", E::path());
+        let mut url = format!("{}/{}", self.base_url, E::path());
+        let query = serde_urlencoded::to_string(query)
+            .map_err(|e| Error::Builder(format!("invalid query: {}", e)))?;
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query);
+        }
 
-        decoder.write_all(&mut bytes)?;
-        buf = decoder.finish()?;
+        self.send(HttpRequest {
+            method: HttpMethod::Get,
+            url,
+            headers: Vec::new(),
+            body: None,
+        })
+        .await
+    }
 
-        tracing::trace!("result length {}", buf.len());
+    /// Performs a single post request to the given Post Endpoint
+    async fn post_raw_once<E: PostEndpoint>(&self, body: Vec<u8>) -> crate::Result<Vec<u8>> {
+        tracing::trace!("POST request to {}", E::path());
 
-        Ok(buf)
+        self.send(HttpRequest {
+            method: HttpMethod::Post,
+            url: format!("{}/{}", self.base_url, E::path()),
+            headers: vec![(
+                String::from("Content-Type"),
+                String::from("application/octet-stream"),
+            )],
+            body: Some(body),
+        })
+        .await
     }
 
-    /// Deserializes the body to the given type
-    #[tracing::instrument(skip(bytes), level = "trace")]
-    fn deserialize_body<T: FromJson>(bytes: Vec<u8>) -> crate::Result<T> {
-        let json_value: serde_json::Value = serde_json::from_reader(&bytes[..])?;
-        tracing::trace!("json value = {}", json_value.to_string());
+    /// Sends the request through the transport after waiting
+    /// for the rate and bandwidth limits
+    async fn send(&self, mut request: HttpRequest) -> crate::Result<Vec<u8>> {
+        self.rate_limiter.wait_for_request().await;
+        self.bandwidth.acquire().await?;
+        request
+            .headers
+            .push((String::from("Hydrus-Key"), self.access_key.to_string()));
+        let response = self.transport.send(request).await?;
+        self.bandwidth.report_data(response.body.len() as u64);
+
+        if response.status.is_success() {
+            return Ok(response.body);
+        }
+        let message = String::from_utf8_lossy(&response.body).into_owned();
 
-        T::from_json(json_value)
+        if response.status.as_u16() == BANDWIDTH_EXCEEDED {
+            Err(Error::RepositoryBandwidthExceeded(
+                message,
+                retry_after(&response.headers),
+            ))
+        } else {
+            Err(Error::ResponseStatus(response.status, message))
+        }
     }
 }
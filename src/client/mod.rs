@@ -1,17 +1,30 @@
 mod client_core;
+mod rate_limiter;
+mod update_cache;
 mod update_stream;
 
+use crate::encoding::decode_body;
 pub use crate::endpoints::*;
-use crate::{ClientBuilder, Result};
-pub use client_core::*;
-use std::fmt::Debug;
+use crate::{
+    BandwidthLimiter, BandwidthRules, BandwidthType, ClientBuilder, Error, HttpTransport,
+    ReqwestTransport, Result, RetryPolicy, Sha256Hash, UpdateSource,
+};
+pub(crate) use rate_limiter::*;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+pub(crate) use update_cache::*;
 pub use update_stream::*;
 
 #[derive(Clone)]
 pub struct Client {
-    pub(crate) client: reqwest::Client,
+    pub(crate) transport: Arc<dyn HttpTransport>,
     pub(crate) base_url: String,
     pub(crate) access_key: String,
+    pub(crate) update_cache: Option<Arc<UpdateCache>>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) bandwidth: Arc<BandwidthLimiter>,
+    pub(crate) rate_limiter: Arc<RateLimiter>,
 }
 
 impl Client {
@@ -24,8 +37,12 @@ impl Client {
     pub fn new<S1: ToString, S2: ToString>(endpoint: S1, access_key: S2) -> Self {
         Self {
             base_url: endpoint.to_string(),
-            client: reqwest::Client::new(),
+            transport: Arc::new(ReqwestTransport::default()),
             access_key: access_key.to_string(),
+            update_cache: None,
+            retry_policy: RetryPolicy::none(),
+            bandwidth: Arc::default(),
+            rate_limiter: Arc::default(),
         }
     }
 
@@ -42,14 +59,94 @@ impl Client {
         self.get::<MetadataEndpoint, _>(&[("since", since)]).await
     }
 
-    /// Returns the parsed update file identified by the given hash.
-    /// The hash can be retrieved by fetching the metadata with [Client::metadata]
+    /// Returns information about all available updates with
+    /// an update index within the given range
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub async fn get_metadata_range(&self, range: Range<u64>) -> Result<MetadataResponse> {
+        let metadata = self.get_metadata(range.start).await?;
+
+        Ok(MetadataResponse(metadata.0.index_range(range)))
+    }
+
+    /// Uploads content changes to the repository
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub async fn post_update(&self, update: &ClientToServerUpdate) -> Result<()> {
+        self.post_raw::<UpdatePostEndpoint>(update).await?;
+
+        Ok(())
+    }
+
+    /// Returns the account of the access key
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub async fn get_account(&self) -> Result<AccountResponse> {
+        self.get::<AccountEndpoint, ()>(&()).await
+    }
+
+    /// Fetches the account of the access key and limits the bandwidth
+    /// of this client to the rules of its account type
     #[tracing::instrument(skip(self), level = "debug")]
-    pub async fn get_update<S: AsRef<str> + Debug>(
+    pub async fn apply_account_bandwidth_rules(&self) -> Result<BandwidthRules> {
+        let rules = self.get_account().await?.bandwidth_rules;
+        self.set_bandwidth_rules(rules.clone());
+
+        Ok(rules)
+    }
+
+    /// Returns the bandwidth rules of the client
+    pub fn bandwidth_rules(&self) -> BandwidthRules {
+        self.bandwidth.rules()
+    }
+
+    /// Replaces the bandwidth rules of this client and all its clones
+    pub fn set_bandwidth_rules(&self, rules: BandwidthRules) {
+        self.bandwidth.set_rules(rules)
+    }
+
+    /// Returns the bandwidth used by this client and all its clones within
+    /// the given time window or in total if no window is given.
+    /// Only usage within the last day or the longest rule window is tracked.
+    pub fn bandwidth_usage(
         &self,
-        update_hash: S,
-    ) -> Result<UpdateResponse> {
-        self.get::<UpdateEndpoint, _>(&[("update_hash", update_hash.as_ref())])
-            .await
+        bandwidth_type: BandwidthType,
+        time_delta: Option<Duration>,
+    ) -> u64 {
+        self.bandwidth.usage(bandwidth_type, time_delta)
+    }
+
+    /// Returns the parsed update file identified by the given hash.
+    /// The hash can be retrieved by fetching the metadata with [Client::get_metadata]
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub async fn get_update(&self, update_hash: &Sha256Hash) -> Result<UpdateResponse> {
+        let bytes = self.get_raw_update(update_hash).await?;
+
+        decode_body(bytes)
+    }
+}
+
+impl UpdateSource for Client {
+    /// Returns the raw update file either from the update cache if configured
+    /// or from the PTR. Downloads that don't match their hash are rejected
+    /// when the cache is configured.
+    async fn get_raw_update(&self, update_hash: &Sha256Hash) -> Result<Vec<u8>> {
+        if let Some(cache) = &self.update_cache {
+            if let Some(bytes) = cache.get(update_hash).await {
+                tracing::debug!("update cache hit for {}", update_hash);
+                return Ok(bytes);
+            }
+        }
+        self.rate_limiter.wait_for_update().await;
+        let bytes = self
+            .get_raw::<UpdateEndpoint, _>(&[("update_hash", update_hash.to_hex())])
+            .await?;
+
+        if let Some(cache) = &self.update_cache {
+            match cache.insert(update_hash, &bytes).await {
+                Err(e @ Error::HashMismatch(..)) => return Err(e),
+                Err(e) => tracing::warn!("failed to cache update {}: {}", update_hash, e),
+                Ok(()) => {}
+            }
+        }
+
+        Ok(bytes)
     }
 }
@@ -0,0 +1,60 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Spaces out requests of a client and all its clones.
+/// Each caller reserves the next free slot so concurrent callers
+/// are served in order instead of all waking up at the same time.
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+    request_interval: Option<Duration>,
+    update_delay: Option<Duration>,
+    state: Mutex<RateLimiterState>,
+}
+
+#[derive(Debug, Default)]
+struct RateLimiterState {
+    next_request: Option<Instant>,
+    next_update: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: Option<f64>, update_delay: Option<Duration>) -> Self {
+        Self {
+            request_interval: requests_per_second.map(|r| Duration::from_secs_f64(1.0 / r)),
+            update_delay,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Waits until the next request is allowed
+    pub async fn wait_for_request(&self) {
+        if let Some(interval) = self.request_interval {
+            let slot = Self::reserve(&mut self.state.lock().unwrap().next_request, interval);
+            Self::sleep_until(slot).await;
+        }
+    }
+
+    /// Waits until the next update download is allowed
+    pub async fn wait_for_update(&self) {
+        if let Some(delay) = self.update_delay {
+            let slot = Self::reserve(&mut self.state.lock().unwrap().next_update, delay);
+            Self::sleep_until(slot).await;
+        }
+    }
+
+    fn reserve(next: &mut Option<Instant>, interval: Duration) -> Instant {
+        let now = Instant::now();
+        let slot = next.map(|n| n.max(now)).unwrap_or(now);
+        *next = Some(slot + interval);
+
+        slot
+    }
+
+    async fn sleep_until(slot: Instant) {
+        if slot > Instant::now() {
+            tracing::trace!("rate limited for {:?}", slot - Instant::now());
+            tokio::time::sleep_until(slot).await;
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{Error, Result, Sha256Hash};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+static TMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// An on-disk cache of raw update files.
+/// Files are stored by their hex encoded hash so the cache directory
+/// can also be read with the [crate::FileSystemUpdateSource].
+/// Least recently used files are evicted once the size limit is exceeded.
+/// Files are verified against their hash when they are read or written.
+#[derive(Debug)]
+pub(crate) struct UpdateCache {
+    directory: PathBuf,
+    size_limit: u64,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<Sha256Hash, CacheEntry>,
+    access_order: BTreeMap<u64, Sha256Hash>,
+    total_size: u64,
+    access_counter: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    size: u64,
+    last_access: u64,
+}
+
+impl UpdateCache {
+    /// Opens the cache in the given directory and indexes existing files.
+    /// Temporary files left behind by interrupted writes are removed.
+    #[tracing::instrument(level = "debug")]
+    pub fn open(directory: PathBuf, size_limit: u64) -> Result<Self> {
+        fs::create_dir_all(&directory)?;
+        let mut files = Vec::new();
+
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+
+            if !metadata.is_file() {
+                continue;
+            }
+            if entry.file_name().to_string_lossy().ends_with(".tmp") {
+                tracing::debug!("removing interrupted write {:?}", entry.path());
+                fs::remove_file(entry.path())?;
+                continue;
+            }
+            if let Ok(hash) = Sha256Hash::from_hex(entry.file_name().to_string_lossy()) {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((modified, hash, metadata.len()));
+            }
+        }
+        files.sort();
+
+        let cache = Self {
+            directory,
+            size_limit,
+            state: Mutex::new(CacheState::default()),
+        };
+        let evicted = {
+            let mut state = cache.state.lock().unwrap();
+            for (_, hash, size) in files {
+                state.insert(hash, size);
+            }
+            cache.evict(&mut state)
+        };
+        for hash in evicted {
+            if let Err(e) = fs::remove_file(cache.path(&hash)) {
+                if e.kind() != ErrorKind::NotFound {
+                    tracing::warn!("failed to evict cached update {}: {}", hash, e);
+                }
+            }
+        }
+        tracing::debug!("indexed {} cached updates", cache.len());
+
+        Ok(cache)
+    }
+
+    /// Returns the cached update file for the given hash
+    #[tracing::instrument(skip(self), level = "trace")]
+    pub async fn get(&self, hash: &Sha256Hash) -> Option<Vec<u8>> {
+        if !self.state.lock().unwrap().touch(hash) {
+            return None;
+        }
+        let path = self.path(hash);
+
+        match tokio::fs::read(&path).await {
+            Ok(bytes) if Sha256Hash::digest(&bytes) != *hash => {
+                tracing::warn!("evicting corrupt cached update {}", hash);
+                self.state.lock().unwrap().remove(hash);
+                if let Err(e) = tokio::fs::remove_file(&path).await {
+                    tracing::warn!("failed to remove corrupt cached update {}: {}", hash, e);
+                }
+                None
+            }
+            Ok(bytes) => {
+                if let Err(e) = Self::set_modified(&path).await {
+                    tracing::debug!("failed to update access time of {:?}: {}", path, e);
+                }
+                Some(bytes)
+            }
+            Err(e) => {
+                tracing::warn!("failed to read cached update {}: {}", hash, e);
+                self.state.lock().unwrap().remove(hash);
+                None
+            }
+        }
+    }
+
+    /// Stores an update file in the cache and evicts the least recently used
+    /// files if the size limit is exceeded. Files that don't match their hash are rejected.
+    #[tracing::instrument(skip(self, bytes), level = "trace")]
+    pub async fn insert(&self, hash: &Sha256Hash, bytes: &[u8]) -> Result<()> {
+        let size = bytes.len() as u64;
+        let actual_hash = Sha256Hash::digest(bytes);
+
+        if actual_hash != *hash {
+            return Err(Error::HashMismatch(*hash, actual_hash));
+        }
+
+        if size > self.size_limit {
+            tracing::debug!("update {} exceeds the cache size limit", hash);
+            return Ok(());
+        }
+        let path = self.path(hash);
+        let tmp_path = self.directory.join(format!(
+            "{}.{}.{}.tmp",
+            hash,
+            std::process::id(),
+            TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&tmp_path, bytes).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+
+        let evicted = {
+            let mut state = self.state.lock().unwrap();
+            state.insert(*hash, size);
+            self.evict(&mut state)
+        };
+        for hash in evicted {
+            if let Err(e) = tokio::fs::remove_file(self.path(&hash)).await {
+                if e.kind() != ErrorKind::NotFound {
+                    tracing::warn!("failed to evict cached update {}: {}", hash, e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of cached files
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Removes the least recently used entries until the size limit is met
+    /// and returns their hashes so that their files can be deleted
+    fn evict(&self, state: &mut CacheState) -> Vec<Sha256Hash> {
+        let mut evicted = Vec::new();
+
+        while state.total_size > self.size_limit {
+            let hash = match state.access_order.values().next() {
+                Some(hash) => *hash,
+                None => break,
+            };
+            state.remove(&hash);
+            tracing::trace!("evicted cached update {}", hash);
+            evicted.push(hash);
+        }
+
+        evicted
+    }
+
+    fn path(&self, hash: &Sha256Hash) -> PathBuf {
+        self.directory.join(hash.to_hex())
+    }
+
+    async fn set_modified(path: &Path) -> std::io::Result<()> {
+        tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .await?
+            .into_std()
+            .await
+            .set_modified(SystemTime::now())
+    }
+}
+
+impl CacheState {
+    fn insert(&mut self, hash: Sha256Hash, size: u64) {
+        self.remove(&hash);
+        self.access_counter += 1;
+        self.access_order.insert(self.access_counter, hash);
+        self.entries.insert(
+            hash,
+            CacheEntry {
+                size,
+                last_access: self.access_counter,
+            },
+        );
+        self.total_size += size;
+    }
+
+    fn touch(&mut self, hash: &Sha256Hash) -> bool {
+        self.access_counter += 1;
+        let access_counter = self.access_counter;
+
+        if let Some(entry) = self.entries.get_mut(hash) {
+            self.access_order.remove(&entry.last_access);
+            entry.last_access = access_counter;
+            self.access_order.insert(access_counter, *hash);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remove(&mut self, hash: &Sha256Hash) {
+        if let Some(entry) = self.entries.remove(hash) {
+            self.access_order.remove(&entry.last_access);
+            self.total_size -= entry.size;
+        }
+    }
+}
@@ -1,6 +1,9 @@
-use crate::Result;
-use crate::{Client, UpdateResponse};
+use crate::constants::HYDRUS_TYPE_CONTENT_UPDATE;
+use crate::encoding::{decode_body, peek_type_id};
+use crate::{Client, MetadataEntry, Sha256Hash, UpdateFilter, UpdateResponse, UpdateSource};
+use crate::{Error, Result};
 use futures_core::Stream;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -8,17 +11,128 @@ use std::task::{Context, Poll};
 impl Client {
     pub async fn stream_updates(&self, since: u64) -> Result<UpdateStream> {
         let entries = self.get_metadata(since).await?.0.entries;
-        let hashes = entries
-            .into_iter()
-            .flat_map(|e| e.update_hashes)
-            .collect::<Vec<String>>();
         let client = self.clone();
 
-        Ok(UpdateStream::new(client, hashes))
+        Ok(UpdateStream::from_entries(client, entries))
     }
 }
 
-/// A stream of update files
+type UpdateFuture<S> = Pin<Box<dyn Future<Output = (Result<Vec<u8>>, S)>>>;
+type ProgressCallback = Box<dyn FnMut(&StreamProgress)>;
+
+/// The progress of an [UpdateStream]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StreamProgress {
+    /// The number of hashes the stream was created with
+    pub total: usize,
+    /// The number of updates retrieved successfully
+    pub completed: usize,
+    /// The number of failed hashes that haven't been retried yet
+    pub failed: usize,
+    /// The number of hashes that are queued or being retrieved
+    pub remaining: usize,
+    /// The number of raw bytes retrieved from the source
+    pub bytes_downloaded: u64,
+    /// The update index of the entry being processed.
+    /// Only known if the stream was created from metadata entries.
+    pub update_index: Option<u64>,
+    /// The start of the time range of the entry being processed
+    pub time_begin: Option<u64>,
+    /// The end of the time range of the entry being processed
+    pub time_end: Option<u64>,
+    /// The number of retrieved content updates held back
+    /// until their definitions have been retrieved
+    pub held_back: usize,
+}
+
+/// An update file yielded by an [UpdateStream] together with
+/// the metadata entry it belongs to
+#[derive(Clone, Debug)]
+pub struct StreamedUpdate {
+    /// The update index of the metadata entry
+    pub update_index: u64,
+    /// The hash of the update file
+    pub hash: Sha256Hash,
+    /// The start of the time range of the metadata entry
+    pub time_begin: u64,
+    /// The end of the time range of the metadata entry
+    pub time_end: u64,
+    /// The parsed update file
+    pub update: UpdateResponse,
+}
+
+/// An update file yielded by a [HashUpdateStream]
+#[derive(Clone, Debug)]
+pub struct RetrievedUpdate {
+    /// The hash of the update file
+    pub hash: Sha256Hash,
+    /// The parsed update file
+    pub update: UpdateResponse,
+}
+
+/// The order in which an [UpdateStream] yields update files
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StreamOrdering {
+    /// Yields update files in the order they are retrieved
+    #[default]
+    AsRetrieved,
+    /// Holds back content updates until all update files of their
+    /// update index and all earlier ones have been retrieved, so that
+    /// the definitions they depend on are always yielded first.
+    /// Content updates whose definitions failed are held back until
+    /// the failed hashes have been retried successfully. If the stream ends
+    /// with content still held back it yields an [Error::HeldBack] naming
+    /// the held back hashes before it ends.
+    DefinitionsFirst,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct EntryContext {
+    update_index: u64,
+    time_begin: u64,
+    time_end: u64,
+}
+
+/// The metadata known about the hashes of a stream
+trait StreamContext: Copy + 'static {
+    /// Returns the metadata entry of the hash if it is known
+    fn entry(&self) -> Option<EntryContext>;
+
+    /// Returns the update index of the hash. Hashes without
+    /// an entry are ordered after all others.
+    fn update_index(&self) -> u64 {
+        self.entry().map(|e| e.update_index).unwrap_or(u64::MAX)
+    }
+}
+
+impl StreamContext for EntryContext {
+    fn entry(&self) -> Option<EntryContext> {
+        Some(*self)
+    }
+}
+
+impl StreamContext for () {
+    fn entry(&self) -> Option<EntryContext> {
+        None
+    }
+}
+
+/// An update file retrieved by a [StreamCore] with the context of its hash
+struct Retrieved<C> {
+    hash: Sha256Hash,
+    context: C,
+    update: UpdateResponse,
+}
+
+/// A raw update file retrieved by a [StreamCore] that hasn't been decoded yet
+struct RawRetrieved<C> {
+    hash: Sha256Hash,
+    context: C,
+    bytes: Vec<u8>,
+}
+
+/// A stream of update files retrieved from an [UpdateSource] in order
+/// of the metadata entries they belong to.
 /// Used like follows:
 ///
 /// ```
@@ -30,80 +144,456 @@ impl Client {
 /// # let client = Client::new("", "");
 /// let mut stream = client.stream_updates(0).await.unwrap();
 ///
-/// while let Some(Ok(update)) = stream.next().await {
-///     // do something
+/// while let Some(Ok(streamed)) = stream.next().await {
+///     println!("update {:?} of index {}", streamed.hash, streamed.update_index);
 /// }
 /// # }
 /// ```
-pub struct UpdateStream {
-    failed_hashes: Vec<String>,
-    pending_hash: Option<String>,
-    hashes: Vec<String>,
-    client: Option<Client>,
-    fut: Option<Pin<Box<dyn Future<Output = (Result<UpdateResponse>, Client)>>>>,
+pub struct UpdateStream<S: UpdateSource + 'static = Client> {
+    core: StreamCore<S, EntryContext>,
+}
+
+/// A stream of update files retrieved from an [UpdateSource] by their
+/// bare hashes without the metadata entries they belong to.
+/// Prefer an [UpdateStream] if the metadata is available.
+pub struct HashUpdateStream<S: UpdateSource + 'static = Client> {
+    core: StreamCore<S, ()>,
+}
+
+/// The retrieval, retry, ordering and filtering shared by all update streams
+struct StreamCore<S: UpdateSource + 'static, C> {
+    failed_hashes: Vec<Sha256Hash>,
+    pending_hash: Option<Sha256Hash>,
+    hashes: Vec<Sha256Hash>,
+    contexts: HashMap<Sha256Hash, C>,
+    source: Option<S>,
+    fut: Option<UpdateFuture<S>>,
+    total: usize,
+    completed: usize,
+    bytes_downloaded: u64,
+    current_entry: Option<EntryContext>,
+    progress_callback: Option<ProgressCallback>,
+    ordering: StreamOrdering,
+    /// Content updates held back as raw bytes, which are much smaller than decoded ones
+    held_back: BTreeMap<u64, VecDeque<RawRetrieved<C>>>,
+    held_back_count: usize,
+    held_back_reported: bool,
+    /// The number of hashes per update index that haven't been retrieved yet
+    outstanding: BTreeMap<u64, usize>,
+    filter: Option<UpdateFilter>,
+    /// Content updates of earlier update indices are skipped without decoding them
+    content_since: u64,
+}
+
+impl<S: UpdateSource + 'static> UpdateStream<S> {
+    /// Creates a new stream retrieving the update files of the given
+    /// metadata entries in order from the given source
+    pub fn from_entries(source: S, entries: Vec<MetadataEntry>) -> Self {
+        let mut hashes = Vec::new();
+
+        for entry in entries {
+            let context = EntryContext {
+                update_index: entry.update_index,
+                time_begin: entry.time_begin,
+                time_end: entry.time_end,
+            };
+            for hash in entry.update_hashes {
+                hashes.push((hash, context));
+            }
+        }
+
+        Self {
+            core: StreamCore::new(source, hashes),
+        }
+    }
+
+    /// Sets the order in which update files are yielded
+    pub fn ordering(mut self, ordering: StreamOrdering) -> Self {
+        self.core.ordering = ordering;
+
+        self
+    }
+
+    /// Discards definitions and content that aren't kept by the given filter
+    /// while update files are decoded. Use it together with
+    /// [StreamOrdering::DefinitionsFirst] so that content is only filtered
+    /// after the definitions it references.
+    pub fn update_filter(mut self, filter: UpdateFilter) -> Self {
+        self.core.filter = Some(filter);
+
+        self
+    }
+
+    /// Returns the filter of the stream with the ids it has kept so far
+    pub fn current_filter(&self) -> Option<&UpdateFilter> {
+        self.core.filter.as_ref()
+    }
+
+    /// Skips the content updates of update indices before the given one without
+    /// decoding them. Their definitions are still yielded to resolve later content.
+    pub fn skip_content_before(mut self, update_index: u64) -> Self {
+        self.core.content_since = update_index;
+
+        self
+    }
+
+    /// Calls the given function with the current progress
+    /// every time an update has been retrieved or failed
+    pub fn on_progress<F: FnMut(&StreamProgress) + 'static>(mut self, callback: F) -> Self {
+        self.core.progress_callback = Some(Box::new(callback));
+
+        self
+    }
+
+    /// Returns the current progress of the stream
+    pub fn progress(&self) -> StreamProgress {
+        self.core.progress()
+    }
+
+    /// Re-enqueues the latest failed hash for retry
+    pub fn retry_latest(&mut self) {
+        self.core.retry_latest()
+    }
+
+    /// Puts all failed hashes back into the queue for retry
+    pub fn retry_all(&mut self) {
+        self.core.retry_all()
+    }
+
+    /// Returns a list of all failed hashes
+    pub fn failed_hashes(&self) -> &Vec<Sha256Hash> {
+        &self.core.failed_hashes
+    }
 }
 
-impl UpdateStream {
-    pub(crate) fn new(client: Client, mut hashes: Vec<String>) -> Self {
+impl<S: UpdateSource + 'static> HashUpdateStream<S> {
+    /// Creates a new stream retrieving the update files with the given hashes
+    /// in order from the given source
+    pub fn new(source: S, hashes: Vec<Sha256Hash>) -> Self {
+        Self {
+            core: StreamCore::new(source, hashes.into_iter().map(|h| (h, ())).collect()),
+        }
+    }
+
+    /// Sets the order in which update files are yielded. Without metadata entries
+    /// [StreamOrdering::DefinitionsFirst] holds back content updates
+    /// until all hashes have been retrieved.
+    pub fn ordering(mut self, ordering: StreamOrdering) -> Self {
+        self.core.ordering = ordering;
+
+        self
+    }
+
+    /// Discards definitions and content that aren't kept by the given filter
+    /// before update files are yielded
+    pub fn update_filter(mut self, filter: UpdateFilter) -> Self {
+        self.core.filter = Some(filter);
+
+        self
+    }
+
+    /// Returns the filter of the stream with the ids it has kept so far
+    pub fn current_filter(&self) -> Option<&UpdateFilter> {
+        self.core.filter.as_ref()
+    }
+
+    /// Calls the given function with the current progress
+    /// every time an update has been retrieved or failed
+    pub fn on_progress<F: FnMut(&StreamProgress) + 'static>(mut self, callback: F) -> Self {
+        self.core.progress_callback = Some(Box::new(callback));
+
+        self
+    }
+
+    /// Returns the current progress of the stream
+    pub fn progress(&self) -> StreamProgress {
+        self.core.progress()
+    }
+
+    /// Re-enqueues the latest failed hash for retry
+    pub fn retry_latest(&mut self) {
+        self.core.retry_latest()
+    }
+
+    /// Puts all failed hashes back into the queue for retry
+    pub fn retry_all(&mut self) {
+        self.core.retry_all()
+    }
+
+    /// Returns a list of all failed hashes
+    pub fn failed_hashes(&self) -> &Vec<Sha256Hash> {
+        &self.core.failed_hashes
+    }
+}
+
+impl<S: UpdateSource + 'static> Unpin for UpdateStream<S> {}
+
+impl<S: UpdateSource + 'static> Stream for UpdateStream<S> {
+    type Item = Result<StreamedUpdate>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.core.poll_next(cx).map(|item| {
+            item.map(|result| {
+                result.map(|retrieved| StreamedUpdate {
+                    update_index: retrieved.context.update_index,
+                    hash: retrieved.hash,
+                    time_begin: retrieved.context.time_begin,
+                    time_end: retrieved.context.time_end,
+                    update: retrieved.update,
+                })
+            })
+        })
+    }
+}
+
+impl<S: UpdateSource + 'static> Unpin for HashUpdateStream<S> {}
+
+impl<S: UpdateSource + 'static> Stream for HashUpdateStream<S> {
+    type Item = Result<RetrievedUpdate>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.core.poll_next(cx).map(|item| {
+            item.map(|result| {
+                result.map(|retrieved| RetrievedUpdate {
+                    hash: retrieved.hash,
+                    update: retrieved.update,
+                })
+            })
+        })
+    }
+}
+
+impl<S: UpdateSource + 'static, C: StreamContext> StreamCore<S, C> {
+    fn new(source: S, hashes: Vec<(Sha256Hash, C)>) -> Self {
+        let contexts = hashes.iter().copied().collect();
+        let mut outstanding = BTreeMap::new();
+        for (_, context) in &hashes {
+            *outstanding.entry(context.update_index()).or_default() += 1;
+        }
+        let mut hashes = hashes.into_iter().map(|(h, _)| h).collect::<Vec<_>>();
         hashes.reverse();
 
         Self {
-            client: Some(client),
+            source: Some(source),
+            total: hashes.len(),
             hashes,
+            contexts,
             fut: None,
             failed_hashes: Vec::new(),
             pending_hash: None,
+            completed: 0,
+            bytes_downloaded: 0,
+            current_entry: None,
+            progress_callback: None,
+            ordering: StreamOrdering::AsRetrieved,
+            held_back: BTreeMap::new(),
+            held_back_count: 0,
+            held_back_reported: false,
+            outstanding,
+            filter: None,
+            content_since: 0,
         }
     }
 
-    /// Re-enqueues the latest failed hash for retry
-    pub fn retry_latest(&mut self) {
+    fn progress(&self) -> StreamProgress {
+        StreamProgress {
+            total: self.total,
+            completed: self.completed,
+            failed: self.failed_hashes.len(),
+            remaining: self.hashes.len() + self.fut.is_some() as usize,
+            bytes_downloaded: self.bytes_downloaded,
+            update_index: self.current_entry.map(|e| e.update_index),
+            time_begin: self.current_entry.map(|e| e.time_begin),
+            time_end: self.current_entry.map(|e| e.time_end),
+            held_back: self.held_back_count,
+        }
+    }
+
+    fn retry_latest(&mut self) {
         if let Some(hash) = self.failed_hashes.pop() {
             self.hashes.push(hash);
+            self.held_back_reported = false;
         }
     }
 
-    /// Puts all failed hashes back into the queue for retry
-    pub fn retry_all(&mut self) {
+    fn retry_all(&mut self) {
+        if !self.failed_hashes.is_empty() {
+            self.held_back_reported = false;
+        }
         self.hashes.append(&mut self.failed_hashes);
     }
 
-    /// Returns a list of all failed hashes
-    pub fn failed_hashes(&self) -> &Vec<String> {
-        &self.failed_hashes
+    /// Removes the first held back content update whose update index
+    /// and all earlier ones have been retrieved completely
+    fn take_releasable(&mut self) -> Option<RawRetrieved<C>> {
+        let mut first = self.held_back.first_entry()?;
+        let min_outstanding = self.outstanding.keys().next();
+        if min_outstanding.is_some_and(|min| *min <= *first.key()) {
+            return None;
+        }
+        let retrieved = first.get_mut().pop_front();
+        if first.get().is_empty() {
+            first.remove();
+        }
+        self.held_back_count -= 1;
+
+        retrieved
     }
-}
 
-impl Unpin for UpdateStream {}
+    /// Marks a hash of the given update index as retrieved
+    fn complete(&mut self, update_index: u64) {
+        if let Some(count) = self.outstanding.get_mut(&update_index) {
+            *count -= 1;
+            if *count == 0 {
+                self.outstanding.remove(&update_index);
+            }
+        }
+    }
 
-impl Stream for UpdateStream {
-    type Item = Result<UpdateResponse>;
+    /// Returns the error reporting the content updates that are still held back
+    /// when no more hashes are queued, once until the failed hashes are retried
+    fn take_held_back_error(&mut self) -> Option<Error> {
+        if self.held_back_count == 0 || self.held_back_reported {
+            return None;
+        }
+        self.held_back_reported = true;
+        let hashes = self
+            .held_back
+            .values()
+            .flatten()
+            .map(|r| r.hash)
+            .collect::<Vec<_>>();
+        tracing::warn!("{} content updates are held back at the end", hashes.len());
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Some(Error::HeldBack(hashes))
+    }
+
+    /// Decodes a raw update file with the filter of the stream.
+    /// Update files that fail to decode are marked as failed.
+    fn decode(&mut self, raw: RawRetrieved<C>) -> Result<Retrieved<C>> {
+        let result = match self.filter.as_mut() {
+            Some(filter) => filter.decode_update(&raw.bytes),
+            None => decode_body(raw.bytes),
+        };
+
+        match result {
+            Ok(update) => Ok(Retrieved {
+                hash: raw.hash,
+                context: raw.context,
+                update,
+            }),
+            Err(e) => {
+                self.failed_hashes.push(raw.hash);
+                Err(e)
+            }
+        }
+    }
+
+    /// Counts a retrieved update file as completed
+    fn mark_completed(&mut self, context: C) {
+        self.completed += 1;
+        self.complete(context.update_index());
+    }
+
+    fn report_progress(&mut self) {
+        if self.progress_callback.is_some() {
+            let progress = self.progress();
+            if let Some(callback) = self.progress_callback.as_mut() {
+                callback(&progress);
+            }
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Retrieved<C>>>> {
+        loop {
+            if let Some(raw) = self.take_releasable() {
+                let result = self.decode(raw);
+                if result.is_err() {
+                    self.completed -= 1;
+                }
+                self.report_progress();
+                return Poll::Ready(Some(result));
+            }
+            match self.poll_next_retrieved(cx) {
+                Poll::Ready(Some(Ok(raw)))
+                    if raw.context.update_index() < self.content_since
+                        && peek_type_id(&raw.bytes) == Some(HYDRUS_TYPE_CONTENT_UPDATE) =>
+                {
+                    tracing::trace!("skipping content update {}", raw.hash);
+                    self.mark_completed(raw.context);
+                    self.report_progress();
+                }
+                Poll::Ready(Some(Ok(raw)))
+                    if self.ordering == StreamOrdering::DefinitionsFirst
+                        && peek_type_id(&raw.bytes) == Some(HYDRUS_TYPE_CONTENT_UPDATE) =>
+                {
+                    tracing::trace!("holding back content update {}", raw.hash);
+                    self.mark_completed(raw.context);
+                    self.held_back
+                        .entry(raw.context.update_index())
+                        .or_default()
+                        .push_back(raw);
+                    self.held_back_count += 1;
+                    self.report_progress();
+                }
+                Poll::Ready(Some(Ok(raw))) => {
+                    let result = self.decode(raw);
+                    if let Ok(retrieved) = &result {
+                        self.mark_completed(retrieved.context);
+                    }
+                    self.report_progress();
+                    return Poll::Ready(Some(result));
+                }
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(self.take_held_back_error().map(Err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    /// Polls the retrieval of the next queued raw update file
+    fn poll_next_retrieved(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<RawRetrieved<C>>>> {
         if self.fut.is_none() {
             if self.hashes.is_empty() {
                 return Poll::Ready(None);
             }
             let hash = self.hashes.pop().unwrap();
-            self.pending_hash = Some(hash.clone());
-            let client = self.client.take().unwrap();
+            self.pending_hash = Some(hash);
+            self.current_entry = self.contexts[&hash].entry();
+            let source = self.source.take().unwrap();
 
             self.fut = Some(Box::pin(async move {
-                let update = client.get_update(hash).await;
+                let bytes = source.get_raw_update(&hash).await;
 
-                (update, client)
+                (bytes, source)
             }));
         }
 
         match self.fut.as_mut().unwrap().as_mut().poll(cx) {
-            Poll::Ready((result, client)) => {
-                self.client = Some(client);
+            Poll::Ready((result, source)) => {
+                self.source = Some(source);
                 self.fut = None;
+                let pending_hash = self.pending_hash.take().unwrap();
 
-                if result.is_err() {
-                    let pending_hash = self.pending_hash.take().unwrap();
-                    self.failed_hashes.push(pending_hash);
-                }
+                let result = match result {
+                    Ok(bytes) => {
+                        self.bytes_downloaded += bytes.len() as u64;
+
+                        Ok(RawRetrieved {
+                            hash: pending_hash,
+                            context: self.contexts[&pending_hash],
+                            bytes,
+                        })
+                    }
+                    Err(e) => {
+                        self.failed_hashes.push(pending_hash);
+                        self.report_progress();
+                        Err(e)
+                    }
+                };
 
                 Poll::Ready(Some(result))
             }
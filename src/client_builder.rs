@@ -1,12 +1,27 @@
 use crate::constants::{DEFAULT_PTR_ADDRESS, DEFAULT_READONLY_ACCESS_KEY};
-use crate::Client;
+use crate::{
+    BandwidthLimiter, BandwidthRules, Client, HttpTransport, RateLimiter, ReqwestTransport,
+    RetryPolicy, UpdateCache,
+};
 use crate::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 pub struct ClientBuilder {
     reqwest_builder: reqwest::ClientBuilder,
     endpoint: String,
     access_key: Option<String>,
+    update_cache: Option<(PathBuf, u64)>,
+    retry_policy: RetryPolicy,
+    bandwidth_rules: BandwidthRules,
+    requests_per_second: Option<f64>,
+    update_delay: Option<Duration>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    /// The options that only apply to the reqwest client
+    reqwest_options: Vec<&'static str>,
+    #[cfg(feature = "rustls")]
+    pinned_certificates: Vec<crate::Sha256Hash>,
 }
 
 impl Default for ClientBuilder {
@@ -15,6 +30,15 @@ impl Default for ClientBuilder {
             reqwest_builder: reqwest::ClientBuilder::new(),
             endpoint: String::from(DEFAULT_PTR_ADDRESS),
             access_key: Some(String::from(DEFAULT_READONLY_ACCESS_KEY)),
+            update_cache: None,
+            retry_policy: RetryPolicy::none(),
+            bandwidth_rules: BandwidthRules::new(),
+            requests_per_second: None,
+            update_delay: None,
+            transport: None,
+            reqwest_options: Vec::new(),
+            #[cfg(feature = "rustls")]
+            pinned_certificates: Vec::new(),
         }
     }
 }
@@ -24,8 +48,69 @@ impl ClientBuilder {
     ///
     /// # Warning
     /// Turning this on allows invalid and expired certificates which is a security risk.
+    /// Prefer [ClientBuilder::add_root_certificate] or pinning the certificate of the
+    /// repository with `pinned_certificate`.
     pub fn accept_invalid_certs(mut self, accept: bool) -> Self {
         self.reqwest_builder = self.reqwest_builder.danger_accept_invalid_certs(accept);
+        self.reqwest_options
+            .retain(|option| *option != "accept_invalid_certs");
+        if accept {
+            self.reqwest_options.push("accept_invalid_certs");
+        }
+
+        self
+    }
+
+    /// Trusts the given root certificate in addition to the system ones.
+    ///
+    /// ```no_run
+    /// # fn a() -> hydrus_ptr_client::Result<()> {
+    /// let certificate = reqwest::Certificate::from_pem(&std::fs::read("server.crt")?)?;
+    /// let client = hydrus_ptr_client::Client::builder()
+    ///     .add_root_certificate(certificate)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.reqwest_builder = self.reqwest_builder.add_root_certificate(certificate);
+        self.reqwest_options.push("add_root_certificate");
+
+        self
+    }
+
+    /// Only accepts server certificates with the given SHA-256 fingerprint.
+    /// Can be called multiple times to pin several certificates.
+    ///
+    /// The chain, hostname and expiry of a pinned certificate aren't checked
+    /// which makes this suitable for the self-signed certificates of hydrus servers.
+    /// Pinning can't be combined with root certificates, invalid certificates
+    /// or client identities.
+    #[cfg(feature = "rustls")]
+    pub fn pinned_certificate(mut self, fingerprint: crate::Sha256Hash) -> Self {
+        self.pinned_certificates.push(fingerprint);
+        self.reqwest_options.push("pinned_certificate");
+
+        self
+    }
+
+    /// Sets the client certificate presented to the server.
+    /// Requires the `rustls` feature. Clients with an identity connect with rustls,
+    /// which only trusts the root certificates added with [ClientBuilder::add_root_certificate].
+    #[cfg(feature = "rustls")]
+    pub fn identity(mut self, identity: reqwest::Identity) -> Self {
+        self.reqwest_builder = self.reqwest_builder.use_rustls_tls().identity(identity);
+        self.reqwest_options.push("identity");
+
+        self
+    }
+
+    /// Sends requests through the given proxy. Can be called multiple
+    /// times to use different proxies for http and https.
+    /// SOCKS proxies require the `socks` feature.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.reqwest_builder = self.reqwest_builder.proxy(proxy);
+        self.reqwest_options.push("proxy");
 
         self
     }
@@ -33,6 +118,7 @@ impl ClientBuilder {
     /// Sets the request timeout
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.reqwest_builder = self.reqwest_builder.timeout(timeout);
+        self.reqwest_options.push("timeout");
 
         self
     }
@@ -53,16 +139,119 @@ impl ClientBuilder {
         self
     }
 
+    /// Caches downloaded update files in the given directory.
+    /// The least recently used files are removed once the size of the cache
+    /// exceeds the given limit in bytes.
+    pub fn update_cache<P: AsRef<Path>>(mut self, directory: P, size_limit: u64) -> Self {
+        self.update_cache = Some((directory.as_ref().to_path_buf(), size_limit));
+
+        self
+    }
+
+    /// Sets the policy used to retry failed requests.
+    /// By default requests are not retried.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+
+        self
+    }
+
+    /// Sets the bandwidth rules of the client. Requests are delayed
+    /// until they are allowed by all rules. The rules of the account
+    /// can be applied later with [Client::apply_account_bandwidth_rules].
+    pub fn bandwidth_rules(mut self, rules: BandwidthRules) -> Self {
+        self.bandwidth_rules = rules;
+
+        self
+    }
+
+    /// Limits the number of requests per second made by
+    /// the client and all its clones
+    pub fn max_requests_per_second(mut self, requests_per_second: f64) -> Self {
+        self.requests_per_second = Some(requests_per_second);
+
+        self
+    }
+
+    /// Sets the minimum delay between the starts of two update downloads
+    /// made by the client and all its clones
+    pub fn update_download_delay(mut self, delay: Duration) -> Self {
+        self.update_delay = Some(delay);
+
+        self
+    }
+
+    /// Sends requests through the given transport instead of reqwest.
+    /// The reqwest specific options like [ClientBuilder::timeout], proxies
+    /// and certificates can't be combined with a custom transport.
+    pub fn transport<T: HttpTransport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+
+        self
+    }
+
+    /// Validates the configuration and builds a blocking client
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<crate::blocking::Client> {
+        crate::blocking::Client::from_client(self.build()?)
+    }
+
     /// Validates the configuration and builds the client
     pub fn build(self) -> Result<Client> {
         let access_key = self
             .access_key
             .ok_or_else(|| Error::Builder(String::from("missing access key")))?;
+        if let Some(rate) = self.requests_per_second {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(Error::Builder(format!("invalid request rate {}", rate)));
+            }
+        }
+        let update_cache = self
+            .update_cache
+            .map(|(directory, size_limit)| UpdateCache::open(directory, size_limit))
+            .transpose()?
+            .map(Arc::new);
+
+        if let (Some(_), Some(option)) = (&self.transport, self.reqwest_options.first()) {
+            return Err(Error::Builder(format!(
+                "{} can't be combined with a custom transport",
+                option
+            )));
+        }
+
+        #[allow(unused_mut)]
+        let mut reqwest_builder = self.reqwest_builder;
+        #[cfg(feature = "rustls")]
+        if !self.pinned_certificates.is_empty() {
+            let conflict = self.reqwest_options.iter().find(|option| {
+                ["identity", "add_root_certificate", "accept_invalid_certs"].contains(option)
+            });
+            if let Some(option) = conflict {
+                return Err(Error::Builder(format!(
+                    "{} can't be combined with pinned certificates",
+                    option
+                )));
+            }
+            reqwest_builder = reqwest_builder
+                .use_preconfigured_tls(crate::tls::pinned_config(self.pinned_certificates));
+        }
+
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => Arc::new(ReqwestTransport::new(reqwest_builder.build()?)),
+        };
 
         Ok(Client {
-            client: self.reqwest_builder.build()?,
+            transport,
             base_url: self.endpoint,
             access_key,
+            update_cache,
+            retry_policy: self.retry_policy,
+            bandwidth: Arc::new(BandwidthLimiter::new(self.bandwidth_rules)),
+            rate_limiter: Arc::new(RateLimiter::new(
+                self.requests_per_second,
+                self.update_delay,
+            )),
         })
     }
 }
@@ -0,0 +1,214 @@
+mod parquet_writer;
+
+use crate::{ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse, Result};
+use arrow_array::builder::{FixedSizeBinaryBuilder, StringBuilder, UInt64Builder, UInt8Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+pub use parquet_writer::*;
+/// Re-exports of the arrow and parquet crates matching the versions used by this crate
+pub use {arrow_array, arrow_schema, parquet};
+
+/// Returns the schema of mapping batches
+pub fn mappings_schema() -> SchemaRef {
+    static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+    SCHEMA
+        .get_or_init(|| {
+            Arc::new(Schema::new(vec![
+                Field::new("tag_id", DataType::UInt64, false),
+                Field::new("hash_id", DataType::UInt64, false),
+                Field::new("action", DataType::UInt8, false),
+                Field::new("update_index", DataType::UInt64, false),
+            ]))
+        })
+        .clone()
+}
+
+/// Returns the schema of tag sibling batches
+pub fn tag_siblings_schema() -> SchemaRef {
+    static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+    SCHEMA
+        .get_or_init(|| tag_pair_schema("tag_id", "sibling_id"))
+        .clone()
+}
+
+/// Returns the schema of tag parent batches
+pub fn tag_parents_schema() -> SchemaRef {
+    static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+    SCHEMA
+        .get_or_init(|| tag_pair_schema("child_id", "parent_id"))
+        .clone()
+}
+
+/// Returns the schema of hash definition batches
+pub fn hash_definitions_schema() -> SchemaRef {
+    static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+    SCHEMA
+        .get_or_init(|| {
+            Arc::new(Schema::new(vec![
+                Field::new("hash_id", DataType::UInt64, false),
+                Field::new("hash", DataType::FixedSizeBinary(32), false),
+                Field::new("update_index", DataType::UInt64, false),
+            ]))
+        })
+        .clone()
+}
+
+/// Returns the schema of tag definition batches
+pub fn tag_definitions_schema() -> SchemaRef {
+    static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+    SCHEMA
+        .get_or_init(|| {
+            Arc::new(Schema::new(vec![
+                Field::new("tag_id", DataType::UInt64, false),
+                Field::new("tag", DataType::Utf8, false),
+                Field::new("update_index", DataType::UInt64, false),
+            ]))
+        })
+        .clone()
+}
+
+fn tag_pair_schema(first: &str, second: &str) -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(first, DataType::UInt64, false),
+        Field::new(second, DataType::UInt64, false),
+        Field::new("action", DataType::UInt8, false),
+        Field::new("update_index", DataType::UInt64, false),
+    ]))
+}
+
+/// Converts the mappings of a content update into a batch with one row per tag and hash
+pub fn mappings_record_batch(
+    content: &ContentUpdateResponse,
+    update_index: u64,
+) -> Result<RecordBatch> {
+    let capacity = content
+        .mappings
+        .values()
+        .flat_map(|m| m.values())
+        .map(Vec::len)
+        .sum();
+    let mut tag_ids = UInt64Builder::with_capacity(capacity);
+    let mut hash_ids = UInt64Builder::with_capacity(capacity);
+    let mut actions = UInt8Builder::with_capacity(capacity);
+
+    for (action, mappings) in &content.mappings {
+        for (tag_id, ids) in mappings {
+            for hash_id in ids {
+                tag_ids.append_value(*tag_id);
+                hash_ids.append_value(*hash_id);
+                actions.append_value(action_value(action));
+            }
+        }
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(tag_ids.finish()),
+        Arc::new(hash_ids.finish()),
+        Arc::new(actions.finish()),
+        update_index_column(update_index, capacity),
+    ];
+
+    Ok(RecordBatch::try_new(mappings_schema(), columns)?)
+}
+
+/// Converts the tag siblings of a content update into a batch
+pub fn tag_siblings_record_batch(
+    content: &ContentUpdateResponse,
+    update_index: u64,
+) -> Result<RecordBatch> {
+    tag_pairs_record_batch(&content.tag_siblings, update_index, tag_siblings_schema())
+}
+
+/// Converts the tag parents of a content update into a batch
+pub fn tag_parents_record_batch(
+    content: &ContentUpdateResponse,
+    update_index: u64,
+) -> Result<RecordBatch> {
+    tag_pairs_record_batch(&content.tag_parents, update_index, tag_parents_schema())
+}
+
+fn tag_pairs_record_batch(
+    pairs: &HashMap<ContentUpdateAction, HashMap<u64, u64>>,
+    update_index: u64,
+    schema: SchemaRef,
+) -> Result<RecordBatch> {
+    let capacity = pairs.values().map(HashMap::len).sum();
+    let mut first_ids = UInt64Builder::with_capacity(capacity);
+    let mut second_ids = UInt64Builder::with_capacity(capacity);
+    let mut actions = UInt8Builder::with_capacity(capacity);
+
+    for (action, entries) in pairs {
+        for (first, second) in entries {
+            first_ids.append_value(*first);
+            second_ids.append_value(*second);
+            actions.append_value(action_value(action));
+        }
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(first_ids.finish()),
+        Arc::new(second_ids.finish()),
+        Arc::new(actions.finish()),
+        update_index_column(update_index, capacity),
+    ];
+
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// Converts the hash definitions of a definitions update into a batch
+pub fn hash_definitions_record_batch(
+    definitions: &DefinitionsUpdateResponse,
+    update_index: u64,
+) -> Result<RecordBatch> {
+    let capacity = definitions.hashes.len();
+    let mut ids = UInt64Builder::with_capacity(capacity);
+    let mut hashes = FixedSizeBinaryBuilder::with_capacity(capacity, 32);
+
+    for (id, hash) in &definitions.hashes {
+        ids.append_value(*id);
+        hashes.append_value(hash.as_bytes())?;
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(ids.finish()),
+        Arc::new(hashes.finish()),
+        update_index_column(update_index, capacity),
+    ];
+
+    Ok(RecordBatch::try_new(hash_definitions_schema(), columns)?)
+}
+
+/// Converts the tag definitions of a definitions update into a batch
+pub fn tag_definitions_record_batch(
+    definitions: &DefinitionsUpdateResponse,
+    update_index: u64,
+) -> Result<RecordBatch> {
+    let capacity = definitions.tags.len();
+    let data_capacity = definitions.tags.values().map(String::len).sum();
+    let mut ids = UInt64Builder::with_capacity(capacity);
+    let mut tags = StringBuilder::with_capacity(capacity, data_capacity);
+
+    for (id, tag) in &definitions.tags {
+        ids.append_value(*id);
+        tags.append_value(tag);
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(ids.finish()),
+        Arc::new(tags.finish()),
+        update_index_column(update_index, capacity),
+    ];
+
+    Ok(RecordBatch::try_new(tag_definitions_schema(), columns)?)
+}
+
+/// Returns the hydrus number of the action which always fits into the action column
+fn action_value(action: &ContentUpdateAction) -> u8 {
+    action.number() as u8
+}
+
+fn update_index_column(update_index: u64, len: usize) -> ArrayRef {
+    let mut builder = UInt64Builder::with_capacity(len);
+    builder.append_value_n(update_index, len);
+
+    Arc::new(builder.finish())
+}
@@ -0,0 +1,123 @@
+use crate::columnar::{
+    hash_definitions_record_batch, mappings_record_batch, tag_definitions_record_batch,
+    tag_parents_record_batch, tag_siblings_record_batch,
+};
+use crate::{Result, UpdateResponse};
+use arrow_array::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+pub const MAPPINGS_TABLE: &str = "mappings";
+pub const TAG_SIBLINGS_TABLE: &str = "tag_siblings";
+pub const TAG_PARENTS_TABLE: &str = "tag_parents";
+pub const HASH_DEFINITIONS_TABLE: &str = "hash_definitions";
+pub const TAG_DEFINITIONS_TABLE: &str = "tag_definitions";
+
+/// Writes updates as parquet files partitioned by their metadata update index.
+/// Every table is written to `<root>/<table>/update_index=<index>/part-<n>.parquet`.
+pub struct ParquetUpdateWriter {
+    root: PathBuf,
+    properties: WriterProperties,
+    part_counters: HashMap<(&'static str, u64), usize>,
+}
+
+impl ParquetUpdateWriter {
+    /// Creates a new writer storing the files in the given directory
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+
+        Self {
+            root: root.as_ref().to_path_buf(),
+            properties,
+            part_counters: HashMap::new(),
+        }
+    }
+
+    /// Sets the properties the parquet files are written with
+    pub fn with_properties(mut self, properties: WriterProperties) -> Self {
+        self.properties = properties;
+
+        self
+    }
+
+    /// Writes the update belonging to the given metadata update index
+    /// and returns the paths of the written files
+    #[tracing::instrument(skip(self, update), level = "debug")]
+    pub fn write_update(
+        &mut self,
+        update_index: u64,
+        update: &UpdateResponse,
+    ) -> Result<Vec<PathBuf>> {
+        let batches = match update {
+            UpdateResponse::Definitions(definitions) => vec![
+                (
+                    HASH_DEFINITIONS_TABLE,
+                    hash_definitions_record_batch(definitions, update_index)?,
+                ),
+                (
+                    TAG_DEFINITIONS_TABLE,
+                    tag_definitions_record_batch(definitions, update_index)?,
+                ),
+            ],
+            UpdateResponse::Content(content) => vec![
+                (
+                    MAPPINGS_TABLE,
+                    mappings_record_batch(content, update_index)?,
+                ),
+                (
+                    TAG_SIBLINGS_TABLE,
+                    tag_siblings_record_batch(content, update_index)?,
+                ),
+                (
+                    TAG_PARENTS_TABLE,
+                    tag_parents_record_batch(content, update_index)?,
+                ),
+            ],
+        };
+        let mut paths = Vec::new();
+
+        for (table, batch) in batches {
+            if batch.num_rows() > 0 {
+                paths.push(self.write_batch(table, update_index, &batch)?);
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Writes a single batch into a new file of the given table partition
+    pub fn write_batch(
+        &mut self,
+        table: &'static str,
+        update_index: u64,
+        batch: &RecordBatch,
+    ) -> Result<PathBuf> {
+        let directory = self
+            .root
+            .join(table)
+            .join(format!("update_index={}", update_index));
+        fs::create_dir_all(&directory)?;
+
+        let counter = self.part_counters.entry((table, update_index)).or_default();
+        let mut path = directory.join(format!("part-{}.parquet", counter));
+        while path.exists() {
+            *counter += 1;
+            path = directory.join(format!("part-{}.parquet", counter));
+        }
+        *counter += 1;
+
+        let file = File::create(&path)?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(self.properties.clone()))?;
+        writer.write(batch)?;
+        writer.close()?;
+        tracing::trace!("wrote {} rows to {:?}", batch.num_rows(), path);
+
+        Ok(path)
+    }
+}
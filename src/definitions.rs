@@ -0,0 +1,46 @@
+use crate::{DefinitionsUpdateResponse, Sha256Hash};
+use std::collections::HashMap;
+
+/// The hash and tag definitions accumulated from definitions updates.
+/// Content updates only reference hashes and tags by their ids which can be
+/// resolved with the definitions seen so far.
+#[derive(Clone, Debug, Default)]
+pub struct Definitions {
+    hashes: HashMap<u64, Sha256Hash>,
+    tags: HashMap<u64, String>,
+}
+
+impl Definitions {
+    /// Creates a new empty set of definitions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the hashes and tags defined in the given update
+    pub fn apply(&mut self, update: &DefinitionsUpdateResponse) {
+        self.hashes
+            .extend(update.hashes.iter().map(|(id, hash)| (*id, *hash)));
+        self.tags
+            .extend(update.tags.iter().map(|(id, tag)| (*id, tag.clone())));
+    }
+
+    /// Returns the hash with the given id
+    pub fn hash(&self, id: u64) -> Option<&Sha256Hash> {
+        self.hashes.get(&id)
+    }
+
+    /// Returns the tag with the given id
+    pub fn tag(&self, id: u64) -> Option<&str> {
+        self.tags.get(&id).map(String::as_str)
+    }
+
+    /// Returns the number of defined hashes
+    pub fn hash_count(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Returns the number of defined tags
+    pub fn tag_count(&self) -> usize {
+        self.tags.len()
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{FromJson, Result, ToJson};
+use flate2::read;
+use flate2::write::{ZlibDecoder, ZlibEncoder};
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// Uses zlib to decompress the body
+#[tracing::instrument(skip(bytes), level = "trace")]
+pub(crate) fn decompress_body(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    tracing::trace!("body length {}", bytes.len());
+
+    let mut buf = Vec::new();
+    let mut decoder = ZlibDecoder::new(buf);
+
+    decoder.write_all(&bytes)?;
+    buf = decoder.finish()?;
+
+    tracing::trace!("result length {}", buf.len());
+
+    Ok(buf)
+}
+
+/// Deserializes the body to the given type
+#[tracing::instrument(skip(bytes), level = "trace")]
+pub(crate) fn deserialize_body<T: FromJson>(bytes: Vec<u8>) -> Result<T> {
+    let json_value: serde_json::Value = serde_json::from_reader(&bytes[..])?;
+    tracing::trace!("json value = {}", json_value.to_string());
+
+    T::from_json(json_value)
+}
+
+/// Decompresses and deserializes a raw body to the given type
+pub(crate) fn decode_body<T: FromJson>(bytes: Vec<u8>) -> Result<T> {
+    let bytes = decompress_body(bytes)?;
+
+    deserialize_body(bytes)
+}
+
+/// Returns the type id of a raw serializable by decompressing only its beginning
+pub(crate) fn peek_type_id(bytes: &[u8]) -> Option<u64> {
+    let mut prefix = Vec::new();
+    read::ZlibDecoder::new(bytes)
+        .take(32)
+        .read_to_end(&mut prefix)
+        .ok()?;
+    let prefix = String::from_utf8_lossy(&prefix);
+
+    prefix
+        .trim_start()
+        .strip_prefix('[')?
+        .trim_start()
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Uses zlib to compress the body
+#[allow(unused)]
+#[tracing::instrument(skip(bytes), level = "trace")]
+pub(crate) fn compress_body(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&bytes)?;
+
+    Ok(encoder.finish()?)
+}
+
+/// Serializes and compresses the given value to a raw body
+#[allow(unused)]
+pub(crate) fn encode_body<T: ToJson>(value: &T) -> Result<Vec<u8>> {
+    let bytes = serde_json::to_vec(&value.to_json()?)?;
+
+    compress_body(bytes)
+}
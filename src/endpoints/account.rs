@@ -0,0 +1,81 @@
+use crate::constants::HYDRUS_TYPE_ACCOUNT_TYPE;
+use crate::hydrus_serializable::bandwidth_rules::HydrusBandwidthRules;
+use crate::hydrus_serializable::dictionary::HydrusDictionary;
+use crate::{BandwidthRules, Endpoint, Error, FromJson, GetEndpoint, Result, ToJson};
+use serde_json::{json, Value};
+
+pub struct AccountEndpoint;
+
+impl Endpoint for AccountEndpoint {
+    fn path() -> &'static str {
+        "account"
+    }
+}
+
+impl GetEndpoint for AccountEndpoint {
+    type Response = AccountResponse;
+}
+
+/// The account of the access key together with the
+/// bandwidth rules of its account type
+#[derive(Clone, Debug)]
+pub struct AccountResponse {
+    pub account_key: String,
+    pub account_type: String,
+    pub created: u64,
+    pub expires: Option<u64>,
+    pub bandwidth_rules: BandwidthRules,
+}
+
+impl FromJson for AccountResponse {
+    fn from_json(value: Value) -> Result<Self> {
+        let mut response = HydrusDictionary::from_json(value)?;
+        let (account_key, account_type, created, expires, _dictionary) =
+            response.take_by_str::<(String, Value, u64, Option<u64>, Value)>("account")?;
+
+        // the account type has had several versions so its fields are read by position
+        let (type_id, _version, account_type): (u64, u64, Vec<Value>) =
+            serde_json::from_value(account_type)?;
+        if type_id != HYDRUS_TYPE_ACCOUNT_TYPE || account_type.len() < 4 {
+            return Err(Error::Malformed);
+        }
+        let title = serde_json::from_value::<String>(account_type[1].clone())?;
+        let bandwidth_rules = HydrusBandwidthRules::from_json(account_type[3].clone())?;
+
+        Ok(Self {
+            account_key,
+            account_type: title,
+            created,
+            expires,
+            bandwidth_rules: BandwidthRules::from_hydrus(bandwidth_rules),
+        })
+    }
+}
+
+impl ToJson for AccountResponse {
+    fn to_json(&self) -> Result<Value> {
+        let account_type = json!([
+            HYDRUS_TYPE_ACCOUNT_TYPE,
+            1,
+            [
+                "",
+                self.account_type,
+                [],
+                self.bandwidth_rules.to_hydrus().to_json()?
+            ]
+        ]);
+        let mut response = HydrusDictionary::new();
+        response.insert_simple(
+            "account",
+            (
+                &self.account_key,
+                account_type,
+                self.created,
+                self.expires,
+                "{}",
+            ),
+        )?;
+
+        response.to_json()
+    }
+}
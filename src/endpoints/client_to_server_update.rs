@@ -0,0 +1,257 @@
+use crate::constants::{
+    CONTENT_TYPE_MAPPINGS, CONTENT_TYPE_TAG_PARENTS, CONTENT_TYPE_TAG_SIBLINGS, HYDRUS_TYPE_CONTENT,
+};
+use crate::hydrus_serializable::client_to_server_update::HydrusClientToServerUpdate;
+use crate::hydrus_serializable::dictionary::HydrusDictionary;
+use crate::hydrus_serializable::wrapper::HydrusSerWrapper;
+use crate::{
+    ContentUpdateAction, Endpoint, Error, FromJson, PostEndpoint, Result, Sha256Hash, ToJson,
+};
+use serde_json::{json, Value};
+
+const CONTENT_UPDATE_ADD: u64 = 0;
+const CONTENT_UPDATE_DELETE: u64 = 1;
+const CONTENT_UPDATE_PEND: u64 = 2;
+const CONTENT_UPDATE_PETITION: u64 = 4;
+
+pub struct UpdatePostEndpoint;
+
+impl Endpoint for UpdatePostEndpoint {
+    fn path() -> &'static str {
+        "update"
+    }
+}
+
+impl PostEndpoint for UpdatePostEndpoint {
+    type Request = ClientToServerUpdate;
+    type Response = ();
+}
+
+/// A piece of content a client wants to add to or delete from a repository
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClientContent {
+    Mappings {
+        tag: String,
+        hashes: Vec<Sha256Hash>,
+    },
+    TagSibling {
+        tag: String,
+        sibling: String,
+    },
+    TagParent {
+        child: String,
+        parent: String,
+    },
+}
+
+/// A change to a piece of content together with the reason for the change
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientContentChange {
+    pub action: ContentUpdateAction,
+    pub content: ClientContent,
+    pub reason: String,
+}
+
+/// The content changes a client uploads to a repository
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientToServerUpdate {
+    pub changes: Vec<ClientContentChange>,
+}
+
+impl ClientToServerUpdate {
+    /// Creates a new empty update
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns if the update doesn't contain any changes
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Adds the tag to the files with the given hashes
+    pub fn add_mappings<S: ToString>(self, tag: S, hashes: Vec<Sha256Hash>) -> Self {
+        self.change(
+            ContentUpdateAction::Add,
+            ClientContent::Mappings {
+                tag: tag.to_string(),
+                hashes,
+            },
+        )
+    }
+
+    /// Removes the tag from the files with the given hashes
+    pub fn delete_mappings<S: ToString>(self, tag: S, hashes: Vec<Sha256Hash>) -> Self {
+        self.change(
+            ContentUpdateAction::Delete,
+            ClientContent::Mappings {
+                tag: tag.to_string(),
+                hashes,
+            },
+        )
+    }
+
+    /// Adds a sibling relationship replacing the tag with its sibling
+    pub fn add_tag_sibling<S1: ToString, S2: ToString>(self, tag: S1, sibling: S2) -> Self {
+        self.change(
+            ContentUpdateAction::Add,
+            ClientContent::TagSibling {
+                tag: tag.to_string(),
+                sibling: sibling.to_string(),
+            },
+        )
+    }
+
+    /// Removes a sibling relationship
+    pub fn delete_tag_sibling<S1: ToString, S2: ToString>(self, tag: S1, sibling: S2) -> Self {
+        self.change(
+            ContentUpdateAction::Delete,
+            ClientContent::TagSibling {
+                tag: tag.to_string(),
+                sibling: sibling.to_string(),
+            },
+        )
+    }
+
+    /// Adds a parent relationship between two tags
+    pub fn add_tag_parent<S1: ToString, S2: ToString>(self, child: S1, parent: S2) -> Self {
+        self.change(
+            ContentUpdateAction::Add,
+            ClientContent::TagParent {
+                child: child.to_string(),
+                parent: parent.to_string(),
+            },
+        )
+    }
+
+    /// Removes a parent relationship between two tags
+    pub fn delete_tag_parent<S1: ToString, S2: ToString>(self, child: S1, parent: S2) -> Self {
+        self.change(
+            ContentUpdateAction::Delete,
+            ClientContent::TagParent {
+                child: child.to_string(),
+                parent: parent.to_string(),
+            },
+        )
+    }
+
+    fn change(mut self, action: ContentUpdateAction, content: ClientContent) -> Self {
+        self.changes.push(ClientContentChange {
+            action,
+            content,
+            reason: String::new(),
+        });
+
+        self
+    }
+
+    /// Returns the serializable content tuple of the given content
+    fn content_json(content: &ClientContent) -> Value {
+        let (content_type, data) = match content {
+            ClientContent::Mappings { tag, hashes } => {
+                (CONTENT_TYPE_MAPPINGS, json!([tag, hashes]))
+            }
+            ClientContent::TagSibling { tag, sibling } => {
+                (CONTENT_TYPE_TAG_SIBLINGS, json!([tag, sibling]))
+            }
+            ClientContent::TagParent { child, parent } => {
+                (CONTENT_TYPE_TAG_PARENTS, json!([child, parent]))
+            }
+        };
+
+        json!([HYDRUS_TYPE_CONTENT, 1, [content_type, data]])
+    }
+
+    /// Parses a serializable content tuple. Returns `None` for
+    /// content types that aren't supported by tag repositories.
+    fn parse_content(value: Value) -> Result<Option<ClientContent>> {
+        let (type_id, _version, (content_type, data)): (u64, u64, (u64, Value)) =
+            serde_json::from_value(value)?;
+        if type_id != HYDRUS_TYPE_CONTENT {
+            return Err(Error::Malformed);
+        }
+        let content = match content_type {
+            CONTENT_TYPE_MAPPINGS => {
+                let (tag, hashes) = serde_json::from_value(data)?;
+                ClientContent::Mappings { tag, hashes }
+            }
+            CONTENT_TYPE_TAG_SIBLINGS => {
+                let (tag, sibling) = serde_json::from_value(data)?;
+                ClientContent::TagSibling { tag, sibling }
+            }
+            CONTENT_TYPE_TAG_PARENTS => {
+                let (child, parent) = serde_json::from_value(data)?;
+                ClientContent::TagParent { child, parent }
+            }
+            other => {
+                tracing::debug!("ignoring unsupported content type {}", other);
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(content))
+    }
+}
+
+impl FromJson for ClientToServerUpdate {
+    fn from_json(value: Value) -> Result<Self> {
+        let mut request = HydrusDictionary::from_json(value)?;
+        let actions = request
+            .take_by_str::<HydrusSerWrapper<HydrusClientToServerUpdate>>("client_to_server_update")?
+            .inner
+            .0;
+        let mut changes = Vec::new();
+
+        for (action, contents) in actions {
+            let action = match action {
+                CONTENT_UPDATE_ADD | CONTENT_UPDATE_PEND => ContentUpdateAction::Add,
+                CONTENT_UPDATE_DELETE | CONTENT_UPDATE_PETITION => ContentUpdateAction::Delete,
+                other => {
+                    tracing::debug!("ignoring unsupported content update action {}", other);
+                    continue;
+                }
+            };
+            for (content, reason) in contents {
+                if let Some(content) = Self::parse_content(content)? {
+                    changes.push(ClientContentChange {
+                        action: action.clone(),
+                        content,
+                        reason,
+                    });
+                }
+            }
+        }
+
+        Ok(Self { changes })
+    }
+}
+
+impl ToJson for ClientToServerUpdate {
+    fn to_json(&self) -> Result<Value> {
+        let mut pends = Vec::new();
+        let mut petitions = Vec::new();
+
+        for change in &self.changes {
+            let entry = (Self::content_json(&change.content), change.reason.clone());
+            match change.action {
+                ContentUpdateAction::Add => pends.push(entry),
+                ContentUpdateAction::Delete => petitions.push(entry),
+            }
+        }
+        let mut actions = Vec::new();
+        if !pends.is_empty() {
+            actions.push((CONTENT_UPDATE_PEND, pends));
+        }
+        if !petitions.is_empty() {
+            actions.push((CONTENT_UPDATE_PETITION, petitions));
+        }
+
+        let mut request = HydrusDictionary::new();
+        request.insert_serializable(
+            "client_to_server_update",
+            &HydrusClientToServerUpdate(actions),
+        )?;
+
+        request.to_json()
+    }
+}
@@ -1,7 +1,7 @@
 use crate::hydrus_serializable::dictionary::HydrusDictionary;
-use crate::hydrus_serializable::metadata::HydrusMetadata;
+use crate::hydrus_serializable::metadata::{HydrusMetadata, MetadataDiff, MetadataEntry};
 use crate::hydrus_serializable::wrapper::HydrusSerWrapper;
-use crate::{Endpoint, FromJson, GetEndpoint};
+use crate::{Endpoint, FromJson, GetEndpoint, Sha256Hash, ToJson};
 use serde_json::Value;
 
 pub struct MetadataEndpoint;
@@ -33,12 +33,36 @@ impl FromJson for MetadataResponse {
     }
 }
 
+impl ToJson for MetadataResponse {
+    fn to_json(&self) -> crate::Result<Value> {
+        let mut dict = HydrusDictionary::new();
+        dict.insert_serializable("metadata_slice", &self.0)?;
+
+        dict.to_json()
+    }
+}
+
 impl MetadataResponse {
-    pub fn update_hashes(&self) -> Vec<&String> {
+    /// Returns the entry with the given update index
+    pub fn entry(&self, update_index: u64) -> Option<&MetadataEntry> {
+        self.0.entry(update_index)
+    }
+
+    /// Returns the highest update index
+    pub fn latest_update_index(&self) -> Option<u64> {
+        self.0.latest_update_index()
+    }
+
+    /// Returns how the metadata differs from a previous snapshot
+    pub fn diff(&self, previous: &MetadataResponse) -> MetadataDiff {
+        self.0.diff(&previous.0)
+    }
+
+    pub fn update_hashes(&self) -> Vec<&Sha256Hash> {
         self.0
             .entries
             .iter()
-            .flat_map(|e| e.update_hashes.iter().collect::<Vec<&String>>())
+            .flat_map(|e| e.update_hashes.iter())
             .collect()
     }
 }
@@ -1,3 +1,5 @@
+mod account;
+mod client_to_server_update;
 mod metadata;
 mod options;
 mod update;
@@ -5,6 +7,8 @@ mod update;
 use crate::Result;
 use std::fmt::Debug;
 
+pub use account::*;
+pub use client_to_server_update::*;
 pub use metadata::*;
 pub use options::*;
 pub use update::*;
@@ -27,3 +31,13 @@ pub trait FromJson {
     where
         Self: Sized;
 }
+
+pub trait ToJson {
+    fn to_json(&self) -> Result<serde_json::Value>;
+}
+
+impl FromJson for () {
+    fn from_json(_: serde_json::Value) -> Result<Self> {
+        Ok(())
+    }
+}
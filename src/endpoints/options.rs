@@ -2,7 +2,8 @@ use crate::hydrus_serializable::dictionary::HydrusDictionary;
 use crate::hydrus_serializable::tag_filter::HydrusTagFilter;
 use crate::hydrus_serializable::wrapper::HydrusSerWrapper;
 use crate::Result;
-use crate::{Endpoint, FromJson, GetEndpoint};
+use crate::{Endpoint, FromJson, GetEndpoint, ToJson};
+use serde::Serialize;
 use serde_json::Value;
 
 pub struct OptionsEndpoint;
@@ -17,7 +18,7 @@ impl GetEndpoint for OptionsEndpoint {
     type Response = OptionsResponse;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct OptionsResponse {
     pub server_message: String,
     pub update_period: u64,
@@ -36,7 +37,7 @@ impl FromJson for OptionsResponse {
         let update_period = service_options.take_by_str::<u64>("update_period")?;
         let nullification_period = service_options.take_by_str::<u64>("nullification_period")?;
         let tag_filter = service_options
-            .take_by_str::<HydrusSerWrapper<HydrusTagFilter>>(&"tag_filter")?
+            .take_by_str::<HydrusSerWrapper<HydrusTagFilter>>("tag_filter")?
             .inner
             .0;
 
@@ -48,3 +49,19 @@ impl FromJson for OptionsResponse {
         })
     }
 }
+
+impl ToJson for OptionsResponse {
+    fn to_json(&self) -> Result<Value> {
+        let mut service_options = HydrusDictionary::new();
+        service_options.insert_simple("server_message", &self.server_message)?;
+        service_options.insert_simple("update_period", self.update_period)?;
+        service_options.insert_simple("nullification_period", self.nullification_period)?;
+        service_options
+            .insert_serializable("tag_filter", &HydrusTagFilter(self.tag_filter.clone()))?;
+
+        let mut response = HydrusDictionary::new();
+        response.insert_serializable("service_options", &service_options)?;
+
+        response.to_json()
+    }
+}
@@ -8,7 +8,8 @@ use crate::hydrus_serializable::definitions_update::{
 use crate::hydrus_serializable::wrapper::GenericHydrusSerWrapper;
 use crate::Error::Malformed;
 use crate::Result;
-use crate::{Endpoint, Error, FromJson, GetEndpoint};
+use crate::{Endpoint, Error, FromJson, GetEndpoint, Sha256Hash};
+use serde::Serialize;
 use serde_json::Value;
 use std::collections::HashMap;
 
@@ -24,7 +25,8 @@ impl GetEndpoint for UpdateEndpoint {
     type Response = UpdateResponse;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum UpdateResponse {
     Definitions(DefinitionsUpdateResponse),
     Content(ContentUpdateResponse),
@@ -58,9 +60,9 @@ trait FromWrapper {
         Self: Sized;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct DefinitionsUpdateResponse {
-    pub hashes: HashMap<u64, String>,
+    pub hashes: HashMap<u64, Sha256Hash>,
     pub tags: HashMap<u64, String>,
 }
 
@@ -82,7 +84,7 @@ impl FromWrapper for DefinitionsUpdateResponse {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct ContentUpdateResponse {
     pub mappings: HashMap<ContentUpdateAction, HashMap<u64, Vec<u64>>>,
     pub tag_parents: HashMap<ContentUpdateAction, HashMap<u64, u64>>,
@@ -181,7 +183,8 @@ impl ContentUpdateResponse {
     }
 }
 
-#[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ContentUpdateAction {
     Add = 0,
     Delete = 1,
@@ -195,4 +198,12 @@ impl ContentUpdateAction {
             _ => Err(Malformed),
         }
     }
+
+    /// Returns the number of the action in update files
+    pub fn number(&self) -> u64 {
+        match self {
+            Self::Add => 0,
+            Self::Delete => 1,
+        }
+    }
 }
@@ -1,3 +1,6 @@
+use crate::Sha256Hash;
+use reqwest::StatusCode;
+use std::time::Duration;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -7,9 +10,18 @@ pub enum Error {
     #[error("reqwest error {0}")]
     Reqwest(#[from] reqwest::Error),
 
+    #[deprecated(
+        note = "error responses are returned as `ResponseStatus` or `RepositoryBandwidthExceeded`"
+    )]
     #[error("api returned error response: {0}")]
     Response(String),
 
+    #[error("api returned error response {0}: {1}")]
+    ResponseStatus(StatusCode, String),
+
+    #[error("repository bandwidth exceeded: {0}")]
+    RepositoryBandwidthExceeded(String, Option<Duration>),
+
     #[error("failed to parse content as json: {0}")]
     JSON(#[from] serde_json::Error),
 
@@ -24,4 +36,32 @@ pub enum Error {
 
     #[error("Missing property {0}")]
     MissingProperty(String),
+
+    #[error("invalid hash {0}")]
+    InvalidHash(String),
+
+    #[error("update file {0} has the hash {1}")]
+    HashMismatch(Sha256Hash, Sha256Hash),
+
+    #[error("content updates {0:?} are held back until the failed updates are retried")]
+    HeldBack(Vec<Sha256Hash>),
+
+    #[error("bandwidth limit exceeded: {0}")]
+    BandwidthExceeded(String),
+
+    #[cfg(feature = "arrow")]
+    #[error("arrow error {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+
+    #[cfg(feature = "arrow")]
+    #[error("parquet error {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    #[cfg(any(feature = "mirror", feature = "server", feature = "test-util"))]
+    #[error("http server error {0}")]
+    Server(#[from] hyper::Error),
+
+    #[cfg(any(feature = "mirror", feature = "server"))]
+    #[error("tls error {0}")]
+    Tls(String),
 }
@@ -0,0 +1,76 @@
+use crate::export::ExportRecord;
+use crate::ContentUpdateAction;
+use std::io::{Result, Write};
+
+const HEADER: [&str; 7] = [
+    "content_type",
+    "action",
+    "hash",
+    "tag",
+    "sibling",
+    "child",
+    "parent",
+];
+
+pub(crate) fn write_header<W: Write>(writer: &mut W) -> Result<()> {
+    write_row(writer, &HEADER)
+}
+
+pub(crate) fn write_record<W: Write>(writer: &mut W, record: &ExportRecord) -> Result<()> {
+    match record {
+        ExportRecord::Mapping { action, hash, tag } => write_row(
+            writer,
+            &[
+                "mapping",
+                action_name(action),
+                &hash.to_hex(),
+                tag,
+                "",
+                "",
+                "",
+            ],
+        ),
+        ExportRecord::TagSibling {
+            action,
+            tag,
+            sibling,
+        } => write_row(
+            writer,
+            &["tag_sibling", action_name(action), "", tag, sibling, "", ""],
+        ),
+        ExportRecord::TagParent {
+            action,
+            child,
+            parent,
+        } => write_row(
+            writer,
+            &["tag_parent", action_name(action), "", "", "", child, parent],
+        ),
+    }
+}
+
+fn action_name(action: &ContentUpdateAction) -> &'static str {
+    match action {
+        ContentUpdateAction::Add => "add",
+        ContentUpdateAction::Delete => "delete",
+    }
+}
+
+fn write_row<W: Write>(writer: &mut W, fields: &[&str]) -> Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        write_field(writer, field)?;
+    }
+    writer.write_all(b"\n")
+}
+
+/// Writes a field and quotes it if it contains separators, quotes or line breaks
+fn write_field<W: Write>(writer: &mut W, field: &str) -> Result<()> {
+    if field.contains([',', '"', '\n', '\r']) {
+        write!(writer, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        writer.write_all(field.as_bytes())
+    }
+}
@@ -0,0 +1,190 @@
+mod csv;
+
+use crate::{
+    ContentUpdateAction, ContentUpdateResponse, Definitions, Result, Sha256Hash, UpdateResponse,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// A single flat content row with its ids resolved to hashes and tags
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "content_type", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Mapping {
+        action: ContentUpdateAction,
+        hash: Sha256Hash,
+        tag: String,
+    },
+    TagSibling {
+        action: ContentUpdateAction,
+        tag: String,
+        sibling: String,
+    },
+    TagParent {
+        action: ContentUpdateAction,
+        child: String,
+        parent: String,
+    },
+}
+
+/// The format records are written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One json object per line
+    JsonLines,
+    /// Comma separated values with a header row and a column for every record field
+    Csv,
+}
+
+/// Writes the content of a sequence of updates as flat records.
+/// Definitions updates are only used to resolve the ids of the content
+/// updates that follow them, so updates have to be passed in order.
+pub struct Exporter<W: Write> {
+    writer: W,
+    format: ExportFormat,
+    definitions: Definitions,
+    header_written: bool,
+    unresolved_count: usize,
+}
+
+impl<W: Write> Exporter<W> {
+    /// Creates a new exporter writing records in the given format to the writer
+    pub fn new(writer: W, format: ExportFormat) -> Self {
+        Self {
+            writer,
+            format,
+            definitions: Definitions::new(),
+            header_written: false,
+            unresolved_count: 0,
+        }
+    }
+
+    /// Returns the definitions seen so far
+    pub fn definitions(&self) -> &Definitions {
+        &self.definitions
+    }
+
+    /// Returns the number of rows that were skipped because their ids
+    /// couldn't be resolved with the definitions seen so far
+    pub fn unresolved_count(&self) -> usize {
+        self.unresolved_count
+    }
+
+    /// Writes the records of the given update and returns the number of written records
+    #[tracing::instrument(skip_all, level = "debug")]
+    pub fn export(&mut self, update: &UpdateResponse) -> Result<usize> {
+        let records = self.records(update);
+
+        for record in &records {
+            self.write_record(record)?;
+        }
+
+        Ok(records.len())
+    }
+
+    /// Returns the resolved records of the given update
+    pub fn records(&mut self, update: &UpdateResponse) -> Vec<ExportRecord> {
+        match update {
+            UpdateResponse::Definitions(definitions) => {
+                self.definitions.apply(definitions);
+                Vec::new()
+            }
+            UpdateResponse::Content(content) => self.resolve_content(content),
+        }
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn into_inner(mut self) -> Result<W> {
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    fn resolve_content(&mut self, content: &ContentUpdateResponse) -> Vec<ExportRecord> {
+        let definitions = &self.definitions;
+        let mut records = Vec::new();
+        let mut unresolved_count = 0;
+
+        for (action, mappings) in sorted_entries(&content.mappings) {
+            for (tag_id, hash_ids) in sorted_entries(mappings) {
+                let tag = match definitions.tag(*tag_id) {
+                    Some(tag) => tag,
+                    None => {
+                        unresolved_count += hash_ids.len();
+                        continue;
+                    }
+                };
+                let mut hash_ids = hash_ids.clone();
+                hash_ids.sort_unstable();
+                for hash_id in hash_ids {
+                    match definitions.hash(hash_id) {
+                        Some(hash) => records.push(ExportRecord::Mapping {
+                            action: action.clone(),
+                            hash: *hash,
+                            tag: tag.to_string(),
+                        }),
+                        None => unresolved_count += 1,
+                    }
+                }
+            }
+        }
+        for (action, siblings) in sorted_entries(&content.tag_siblings) {
+            for (tag_id, sibling_id) in sorted_entries(siblings) {
+                match (definitions.tag(*tag_id), definitions.tag(*sibling_id)) {
+                    (Some(tag), Some(sibling)) => records.push(ExportRecord::TagSibling {
+                        action: action.clone(),
+                        tag: tag.to_string(),
+                        sibling: sibling.to_string(),
+                    }),
+                    _ => unresolved_count += 1,
+                }
+            }
+        }
+        for (action, parents) in sorted_entries(&content.tag_parents) {
+            for (child_id, parent_id) in sorted_entries(parents) {
+                match (definitions.tag(*child_id), definitions.tag(*parent_id)) {
+                    (Some(child), Some(parent)) => records.push(ExportRecord::TagParent {
+                        action: action.clone(),
+                        child: child.to_string(),
+                        parent: parent.to_string(),
+                    }),
+                    _ => unresolved_count += 1,
+                }
+            }
+        }
+        if unresolved_count > 0 {
+            tracing::debug!("{} rows couldn't be resolved", unresolved_count);
+        }
+        self.unresolved_count += unresolved_count;
+
+        records
+    }
+
+    fn write_record(&mut self, record: &ExportRecord) -> Result<()> {
+        match self.format {
+            ExportFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                self.writer.write_all(b"\n")?;
+            }
+            ExportFormat::Csv => {
+                if !self.header_written {
+                    csv::write_header(&mut self.writer)?;
+                    self.header_written = true;
+                }
+                csv::write_record(&mut self.writer, record)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the entries of the map sorted by their key so that
+/// exports of the same updates are identical
+fn sorted_entries<K: Ord, V>(map: &HashMap<K, V>) -> Vec<(&K, &V)> {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+    entries
+}
@@ -0,0 +1,117 @@
+use crate::{Error, Result};
+use serde::de::{Error as DeError, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+/// A SHA-256 hash as used by hydrus to identify update files and files
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sha256Hash([u8; 32]);
+
+impl Sha256Hash {
+    /// Creates a new hash from its raw bytes
+    pub const fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Computes the hash of the given data
+    pub fn digest<B: AsRef<[u8]>>(data: B) -> Self {
+        Self(Sha256::digest(data.as_ref()).into())
+    }
+
+    /// Parses a hash from its hex representation
+    pub fn from_hex<S: AsRef<str>>(hex_str: S) -> Result<Self> {
+        let hex_str = hex_str.as_ref();
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(hex_str, &mut bytes)
+            .map_err(|e| Error::InvalidHash(format!("{}: {}", hex_str, e)))?;
+
+        Ok(Self(bytes))
+    }
+
+    /// Returns the lowercase hex representation of the hash
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Returns the raw bytes of the hash
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Sha256Hash {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Sha256Hash {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl FromStr for Sha256Hash {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_hex(s)
+    }
+}
+
+impl TryFrom<&str> for Sha256Hash {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        Self::from_hex(value)
+    }
+}
+
+impl Display for Sha256Hash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl Debug for Sha256Hash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sha256Hash({})", self.to_hex())
+    }
+}
+
+impl Serialize for Sha256Hash {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Sha256Hash {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(HashVisitor)
+    }
+}
+
+struct HashVisitor;
+
+impl<'de> Visitor<'de> for HashVisitor {
+    type Value = Sha256Hash;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "a hex encoded sha256 hash")
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+    where
+        E: DeError,
+    {
+        Sha256Hash::from_hex(v).map_err(E::custom)
+    }
+}
@@ -0,0 +1,36 @@
+#[cfg(any(feature = "mirror", feature = "server"))]
+mod session;
+#[cfg(any(feature = "mirror", feature = "server"))]
+mod tls;
+
+#[cfg(any(feature = "mirror", feature = "server"))]
+pub(crate) use session::*;
+#[cfg(any(feature = "mirror", feature = "server"))]
+pub use tls::*;
+
+use hyper::{Body, Response, StatusCode};
+
+/// Returns the value of the given parameter in the query string
+pub(crate) fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Returns a response with the given raw body
+pub(crate) fn bytes_response(bytes: Vec<u8>) -> Response<Body> {
+    Response::builder()
+        .header(hyper::header::CONTENT_TYPE, "application/octet-stream")
+        .body(Body::from(bytes))
+        .unwrap()
+}
+
+/// Returns a response with the given status and error message
+pub(crate) fn error_response<S: Into<Body>>(status: StatusCode, message: S) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(message.into())
+        .unwrap()
+}
@@ -0,0 +1,97 @@
+use crate::encoding::compress_body;
+use crate::http_server::bytes_response;
+use crate::hydrus_serializable::dictionary::HydrusDictionary;
+use crate::{Error, Result, ToJson};
+use hyper::header::{COOKIE, SET_COOKIE};
+use hyper::{Body, Request, Response};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const ACCESS_KEY_HEADER: &str = "Hydrus-Key";
+const SESSION_KEY_COOKIE: &str = "session_key";
+const SESSION_KEY_LIFETIME: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// The session keys handed out by a server with the access key they were
+/// created for and the time they expire
+#[derive(Debug, Default)]
+pub(crate) struct SessionKeys {
+    keys: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl SessionKeys {
+    /// Returns if the given session key has been created and hasn't expired yet
+    pub fn contains(&self, session_key: &str) -> bool {
+        self.access_key(session_key).is_some()
+    }
+
+    /// Returns the access key the given session key was created for
+    /// if it hasn't expired yet
+    pub fn access_key(&self, session_key: &str) -> Option<String> {
+        let mut keys = self.keys.lock().unwrap();
+        keys.retain(|_, (_, expires)| *expires > Instant::now());
+
+        keys.get(session_key)
+            .map(|(access_key, _)| access_key.clone())
+    }
+
+    /// Creates a new session key for the access key and returns it with its lifetime
+    pub fn create(&self, access_key: &str) -> Result<(String, Duration)> {
+        let mut bytes = [0u8; 32];
+        getrandom::getrandom(&mut bytes).map_err(|e| std::io::Error::other(e.to_string()))?;
+        let session_key = hex::encode(bytes);
+        self.keys.lock().unwrap().insert(
+            session_key.clone(),
+            (
+                access_key.to_string(),
+                Instant::now() + SESSION_KEY_LIFETIME,
+            ),
+        );
+
+        Ok((session_key, SESSION_KEY_LIFETIME))
+    }
+
+    /// Creates a new session key and returns the response handing it
+    /// to the client as a cookie the way hydrus servers do
+    pub fn create_response(&self, access_key: &str) -> Result<Response<Body>> {
+        let (session_key, lifetime) = self.create(access_key)?;
+        let mut body = HydrusDictionary::new();
+        body.insert_simple(SESSION_KEY_COOKIE, &session_key)?;
+        let body = compress_body(body.to_json()?.to_string().into_bytes())?;
+
+        let mut response = bytes_response(body);
+        let cookie = format!(
+            "{}={}; Max-Age={}; Path=/",
+            SESSION_KEY_COOKIE,
+            session_key,
+            lifetime.as_secs()
+        );
+        response
+            .headers_mut()
+            .insert(SET_COOKIE, cookie.parse().map_err(|_| Error::Malformed)?);
+
+        Ok(response)
+    }
+}
+
+/// Returns the access key sent with the request
+pub(crate) fn access_key_header(request: &Request<Body>) -> Option<String> {
+    request
+        .headers()
+        .get(ACCESS_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+}
+
+/// Returns the session key cookie sent with the request
+pub(crate) fn session_key_cookie(request: &Request<Body>) -> Option<String> {
+    request
+        .headers()
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|c| c.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_KEY_COOKIE)
+        .map(|(_, value)| value.to_string())
+}
@@ -0,0 +1,106 @@
+use crate::{Error, Result};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Request, Response};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls_pemfile::Item;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+/// The delay before accepting connections again after accepting one failed
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(100);
+
+/// The certificate and private key a server uses for https.
+/// Hydrus clients only connect to repositories over https.
+#[derive(Clone)]
+pub struct ServerTls {
+    config: Arc<ServerConfig>,
+}
+
+impl ServerTls {
+    /// Loads the PEM encoded certificate chain and private key
+    pub fn from_pem(certificate: &[u8], key: &[u8]) -> Result<Self> {
+        let certificates = rustls_pemfile::certs(&mut &*certificate)?
+            .into_iter()
+            .map(Certificate)
+            .collect::<Vec<_>>();
+        if certificates.is_empty() {
+            return Err(Error::Tls(String::from("no certificate found")));
+        }
+        let key = rustls_pemfile::read_all(&mut &*key)?
+            .into_iter()
+            .find_map(|item| match item {
+                Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(key),
+                _ => None,
+            })
+            .ok_or_else(|| Error::Tls(String::from("no private key found")))?;
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certificates, PrivateKey(key))
+            .map_err(|e| Error::Tls(e.to_string()))?;
+
+        Ok(Self {
+            config: Arc::new(config),
+        })
+    }
+
+    /// Loads the PEM encoded certificate chain and private key from the given files
+    pub fn from_pem_files<P1: AsRef<Path>, P2: AsRef<Path>>(
+        certificate_path: P1,
+        key_path: P2,
+    ) -> Result<Self> {
+        Self::from_pem(&std::fs::read(certificate_path)?, &std::fs::read(key_path)?)
+    }
+}
+
+/// Serves requests over https on the given address. Failing to accept a connection
+/// is logged and doesn't stop the server.
+pub(crate) async fn serve_tls<F, Fut>(address: SocketAddr, tls: ServerTls, handler: F) -> Result<()>
+where
+    F: Fn(Request<Body>) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Response<Body>> + Send + 'static,
+{
+    let acceptor = TlsAcceptor::from(tls.config);
+    let listener = TcpListener::bind(address).await?;
+    tracing::info!("serving https on {}", address);
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                // errors like running out of file descriptors or aborted connections
+                // only affect a single connection
+                tracing::warn!("failed to accept a connection: {}", e);
+                tokio::time::sleep(ACCEPT_ERROR_DELAY).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let handler = handler.clone();
+
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::debug!("tls handshake with {} failed: {}", peer, e);
+                    return;
+                }
+            };
+            let service = service_fn(move |request| {
+                let handler = handler.clone();
+
+                async move { Ok::<_, Infallible>(handler(request).await) }
+            });
+            if let Err(e) = Http::new().serve_connection(stream, service).await {
+                tracing::debug!("connection with {} failed: {}", peer, e);
+            }
+        });
+    }
+}
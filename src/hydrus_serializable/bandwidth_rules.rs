@@ -0,0 +1,13 @@
+use crate::constants::HYDRUS_TYPE_BANDWIDTH_RULES;
+use crate::hydrus_serializable::HydrusSerializable;
+use serde::{Deserialize, Serialize};
+
+/// The list of `(bandwidth_type, time_delta, max_allowed)` rules
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HydrusBandwidthRules(pub Vec<(u8, Option<u64>, u64)>);
+
+impl HydrusSerializable for HydrusBandwidthRules {
+    fn type_id() -> u64 {
+        HYDRUS_TYPE_BANDWIDTH_RULES
+    }
+}
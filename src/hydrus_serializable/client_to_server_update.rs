@@ -0,0 +1,14 @@
+use crate::constants::HYDRUS_TYPE_CLIENT_TO_SERVER_UPDATE;
+use crate::hydrus_serializable::HydrusSerializable;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The list of `(action, [(content, reason)])` pairs uploaded by a client
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HydrusClientToServerUpdate(pub Vec<(u64, Vec<(Value, String)>)>);
+
+impl HydrusSerializable for HydrusClientToServerUpdate {
+    fn type_id() -> u64 {
+        HYDRUS_TYPE_CLIENT_TO_SERVER_UPDATE
+    }
+}
@@ -1,6 +1,6 @@
 use crate::constants::HYDRUS_TYPE_DEFINITIONS_UPDATE;
 use crate::hydrus_serializable::HydrusSerializable;
-use crate::{Error, Result};
+use crate::{Error, Result, Sha256Hash};
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde_json::Value;
@@ -49,7 +49,7 @@ pub trait DefinitionsTrait: DeserializeOwned {
 #[derive(Deserialize, Clone, Debug)]
 pub struct HashDefinition {
     pub id: u64,
-    pub hash: String,
+    pub hash: Sha256Hash,
 }
 
 impl DefinitionsTrait for HashDefinition {
@@ -1,12 +1,12 @@
 #![allow(unused)]
 use crate::constants::HYDRUS_TYPE_DICTIONARY;
 use crate::hydrus_serializable::HydrusSerializable;
-use crate::{Error, Result};
+use crate::{Error, Result, ToJson};
 use serde::de::DeserializeOwned;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value;
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize)]
 pub struct HydrusDictionary {
     list_sim_sim: Vec<(Value, Value)>,
     list_sim_ser: Vec<(Value, Value)>,
@@ -20,7 +20,42 @@ impl HydrusSerializable for HydrusDictionary {
     }
 }
 
+impl Serialize for HydrusDictionary {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            &self.list_sim_sim,
+            &self.list_sim_ser,
+            &self.list_ser_sim,
+            &self.list_ser_ser,
+        )
+            .serialize(serializer)
+    }
+}
+
 impl HydrusDictionary {
+    /// Creates a new empty dictionary
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a simple value with the given string key
+    pub fn insert_simple<V: Serialize>(&mut self, key: &str, value: V) -> Result<()> {
+        self.list_sim_sim
+            .push((key.into(), serde_json::to_value(value)?));
+
+        Ok(())
+    }
+
+    /// Inserts a hydrus serializable value with the given string key
+    pub fn insert_serializable<V: ToJson>(&mut self, key: &str, value: &V) -> Result<()> {
+        self.list_sim_ser.push((key.into(), value.to_json()?));
+
+        Ok(())
+    }
+
     /// Returns the first value for a given key
     pub fn get(&self, key: &Value) -> Option<&Value> {
         self._get(key).into_iter().next()
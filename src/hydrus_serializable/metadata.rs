@@ -1,6 +1,9 @@
 use crate::constants::HYDRUS_TYPE_METADATA;
 use crate::hydrus_serializable::HydrusSerializable;
-use serde::Deserialize;
+use crate::Sha256Hash;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::ops::RangeBounds;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct HydrusMetadata {
@@ -8,10 +11,10 @@ pub struct HydrusMetadata {
     pub next_update_due: u64,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
 pub struct MetadataEntry {
     pub update_index: u64,
-    pub update_hashes: Vec<String>,
+    pub update_hashes: Vec<Sha256Hash>,
     pub time_begin: u64,
     pub time_end: u64,
 }
@@ -21,3 +24,121 @@ impl HydrusSerializable for HydrusMetadata {
         HYDRUS_TYPE_METADATA
     }
 }
+
+impl Serialize for HydrusMetadata {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (&self.entries, self.next_update_due).serialize(serializer)
+    }
+}
+
+impl Serialize for MetadataEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            self.update_index,
+            &self.update_hashes,
+            self.time_begin,
+            self.time_end,
+        )
+            .serialize(serializer)
+    }
+}
+
+/// The difference between two metadata snapshots
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetadataDiff {
+    /// Entries with an update index missing in the previous snapshot
+    pub added: Vec<MetadataEntry>,
+    /// Entries whose update hashes or time range differ from the previous snapshot
+    pub changed: Vec<MetadataEntry>,
+    /// Update indices of the previous snapshot missing in the current one
+    pub removed: Vec<u64>,
+}
+
+impl MetadataDiff {
+    /// Returns if both snapshots contain the same entries
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl HydrusMetadata {
+    /// Returns a copy of the metadata only containing entries
+    /// with an update index greater or equal to the given one
+    pub fn since(&self, since: u64) -> Self {
+        self.index_range(since..)
+    }
+
+    /// Returns a copy of the metadata only containing entries
+    /// with an update index within the given range
+    pub fn index_range<R: RangeBounds<u64>>(&self, range: R) -> Self {
+        self.filter(|e| range.contains(&e.update_index))
+    }
+
+    /// Returns a copy of the metadata only containing entries whose
+    /// time range overlaps the time from `begin` up to `end` in unix timestamps
+    pub fn time_range(&self, begin: u64, end: u64) -> Self {
+        self.filter(|e| e.time_begin < end && e.time_end > begin)
+    }
+
+    /// Returns the entry with the given update index
+    pub fn entry(&self, update_index: u64) -> Option<&MetadataEntry> {
+        self.entries.iter().find(|e| e.update_index == update_index)
+    }
+
+    /// Returns the entry whose time range contains the given unix timestamp
+    pub fn entry_at(&self, timestamp: u64) -> Option<&MetadataEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.time_begin <= timestamp && timestamp < e.time_end)
+    }
+
+    /// Returns the highest update index
+    pub fn latest_update_index(&self) -> Option<u64> {
+        self.entries.iter().map(|e| e.update_index).max()
+    }
+
+    /// Returns how the metadata differs from a previous snapshot
+    pub fn diff(&self, previous: &HydrusMetadata) -> MetadataDiff {
+        let mut previous_entries = previous
+            .entries
+            .iter()
+            .map(|e| (e.update_index, e))
+            .collect::<HashMap<_, _>>();
+        let mut diff = MetadataDiff::default();
+
+        for entry in &self.entries {
+            match previous_entries.remove(&entry.update_index) {
+                None => diff.added.push(entry.clone()),
+                Some(previous_entry) if previous_entry != entry => diff.changed.push(entry.clone()),
+                Some(_) => {}
+            }
+        }
+        // the entries left over weren't matched by any current entry
+        diff.removed = previous
+            .entries
+            .iter()
+            .map(|e| e.update_index)
+            .filter(|index| previous_entries.contains_key(index))
+            .collect();
+
+        diff
+    }
+
+    fn filter<F: Fn(&MetadataEntry) -> bool>(&self, predicate: F) -> Self {
+        Self {
+            entries: self
+                .entries
+                .iter()
+                .filter(|e| predicate(e))
+                .cloned()
+                .collect(),
+            next_update_due: self.next_update_due,
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use crate::hydrus_serializable::wrapper::HydrusSerWrapper;
-use crate::FromJson;
+use crate::{FromJson, ToJson};
 use serde::de::{DeserializeOwned, Error, Visitor};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use std::fmt::Formatter;
 use std::marker::PhantomData;
 
+pub mod bandwidth_rules;
+pub mod client_to_server_update;
 pub mod content_update;
 pub mod definitions_update;
 pub mod dictionary;
@@ -31,6 +33,17 @@ where
     }
 }
 
+impl<T> ToJson for T
+where
+    T: HydrusSerializable + Serialize + Clone,
+{
+    fn to_json(&self) -> crate::Result<Value> {
+        let wrapper = HydrusSerWrapper::new(self.clone());
+
+        serde_json::to_value(wrapper).map_err(crate::Error::from)
+    }
+}
+
 pub trait ConstNumberTrait {
     fn value() -> u64;
 }
@@ -47,6 +60,27 @@ where
 #[derive(Clone, Debug)]
 pub struct SerializableId<T: ConstNumberTrait>(u64, PhantomData<T>);
 
+impl<T: ConstNumberTrait> SerializableId<T> {
+    pub fn new() -> Self {
+        Self(T::value(), PhantomData)
+    }
+}
+
+impl<T: ConstNumberTrait> Default for SerializableId<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ConstNumberTrait> Serialize for SerializableId<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(self.0)
+    }
+}
+
 impl<'de, T: ConstNumberTrait> Deserialize<'de> for SerializableId<T> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
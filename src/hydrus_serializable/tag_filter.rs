@@ -1,9 +1,9 @@
 use crate::constants::HYDRUS_TYPE_TAG_FILTER;
 use crate::hydrus_serializable::HydrusSerializable;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct HydrusTagFilter(pub Value);
 
 impl HydrusSerializable for HydrusTagFilter {
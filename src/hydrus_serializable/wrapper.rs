@@ -1,6 +1,6 @@
 use crate::hydrus_serializable::{ConstNumberTrait, HydrusSerializable, SerializableId};
 use crate::{Error, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value;
 
 #[derive(Clone, Debug, Deserialize)]
@@ -17,11 +17,30 @@ impl ConstNumberTrait for VersionOne {
 pub struct HydrusSerWrapper<T: HydrusSerializable> {
     #[allow(unused)]
     pub type_id: SerializableId<T>,
-    #[allow(unused)]
     pub version: SerializableId<VersionOne>,
     pub inner: T,
 }
 
+impl<T: HydrusSerializable> HydrusSerWrapper<T> {
+    /// Wraps the given value with its type id and version
+    pub fn new(inner: T) -> Self {
+        Self {
+            type_id: SerializableId::new(),
+            version: SerializableId::new(),
+            inner,
+        }
+    }
+}
+
+impl<T: HydrusSerializable + Serialize> Serialize for HydrusSerWrapper<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (&self.type_id, &self.version, &self.inner).serialize(serializer)
+    }
+}
+
 /// A generic hydrus serializable wrapper that allows one
 /// to retrieve the type id and act on that
 #[derive(Clone, Debug, Deserialize)]
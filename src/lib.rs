@@ -1,12 +1,61 @@
 #![doc=include_str!("../README.md")]
 
+mod autocomplete;
+mod bandwidth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod client;
 mod client_builder;
+#[cfg(feature = "arrow")]
+pub mod columnar;
 pub(crate) mod constants;
+mod definitions;
+pub(crate) mod encoding;
 mod endpoints;
 mod error;
+mod export;
+mod hash;
+#[cfg(any(feature = "mirror", feature = "server", feature = "test-util"))]
+pub(crate) mod http_server;
 pub(crate) mod hydrus_serializable;
+#[cfg(feature = "mirror")]
+mod mirror;
+mod report;
+mod retry;
+#[cfg(feature = "server")]
+mod server;
+mod storage;
+mod tag_index;
+mod tag_siblings;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "rustls")]
+pub(crate) mod tls;
+mod transport;
+mod update_file;
+mod update_filter;
+mod update_source;
 
+pub use autocomplete::*;
+pub use bandwidth::*;
 pub use client::*;
 pub use client_builder::*;
+pub use definitions::*;
 pub use error::*;
+pub use export::*;
+pub use hash::*;
+#[cfg(any(feature = "mirror", feature = "server"))]
+pub use http_server::ServerTls;
+pub use hydrus_serializable::metadata::{HydrusMetadata, MetadataDiff, MetadataEntry};
+#[cfg(feature = "mirror")]
+pub use mirror::*;
+pub use report::*;
+pub use retry::*;
+#[cfg(feature = "server")]
+pub use server::*;
+pub use storage::*;
+pub use tag_index::*;
+pub use transport::*;
+pub use update_file::*;
+pub use update_filter::*;
+pub use update_source::*;
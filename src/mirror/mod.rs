@@ -0,0 +1,100 @@
+mod service;
+
+use crate::http_server::SessionKeys;
+use crate::{
+    AccountEndpoint, Client, MetadataResponse, PtrStorage, Result, ServerTls, SyncSummary,
+};
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MIN_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// A local mirror of a PTR that syncs the options, metadata and update files
+/// from an upstream PTR and serves them with the same http api.
+/// The account of the upstream access key is served to every client.
+/// Clients can use any access key to acquire a session key as the mirror is read-only.
+pub struct Mirror {
+    client: Client,
+    storage: PtrStorage,
+    metadata: RwLock<Option<MetadataResponse>>,
+    sessions: SessionKeys,
+}
+
+impl Mirror {
+    /// Creates a new mirror of the PTR the client connects to storing
+    /// the synced data in the given storage
+    pub fn new(client: Client, storage: PtrStorage) -> Result<Self> {
+        let metadata = storage.metadata()?;
+
+        Ok(Self {
+            client,
+            storage,
+            metadata: RwLock::new(metadata),
+            sessions: SessionKeys::default(),
+        })
+    }
+
+    /// Returns the storage of the mirror
+    pub fn storage(&self) -> &PtrStorage {
+        &self.storage
+    }
+
+    /// Fetches the options, account and new metadata of the upstream PTR and
+    /// downloads all update files that aren't stored yet
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub async fn sync(&self) -> Result<SyncSummary> {
+        let summary = self.storage.sync(&self.client).await?;
+        *self.metadata.write().unwrap() = self.storage.metadata()?;
+
+        match self.client.get_raw::<AccountEndpoint, _>(&()).await {
+            Ok(account) => self.storage.write_account(&account)?,
+            Err(e) => tracing::warn!("failed to fetch the upstream account: {}", e),
+        }
+
+        Ok(summary)
+    }
+
+    /// Returns the duration until the next sync should be made according
+    /// to the metadata of the upstream PTR
+    pub fn next_sync_in(&self) -> Duration {
+        let next_update_due = self
+            .metadata
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|m| m.0.next_update_due);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        next_update_due
+            .map(|due| Duration::from_secs(due.saturating_sub(now)).max(MIN_SYNC_INTERVAL))
+            .unwrap_or(DEFAULT_SYNC_INTERVAL)
+    }
+
+    /// Syncs with the upstream PTR whenever new updates are due
+    pub async fn sync_forever(&self) {
+        loop {
+            if let Err(e) = self.sync().await {
+                tracing::error!("failed to sync with the upstream ptr: {}", e);
+            }
+            let delay = self.next_sync_in();
+            tracing::debug!("next sync in {:?}", delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Serves the synced data over plain http on the given address.
+    /// Hydrus clients only connect over https, see [Mirror::serve_tls].
+    pub async fn serve(self: Arc<Self>, address: SocketAddr) -> Result<()> {
+        service::serve(self, address).await
+    }
+
+    /// Serves the synced data over https on the given address
+    pub async fn serve_tls(self: Arc<Self>, address: SocketAddr, tls: ServerTls) -> Result<()> {
+        service::serve_tls(self, address, tls).await
+    }
+}
@@ -0,0 +1,122 @@
+use crate::encoding::encode_body;
+use crate::http_server::{
+    access_key_header, bytes_response, error_response, query_param, serve_tls as serve_https,
+    session_key_cookie,
+};
+use crate::mirror::Mirror;
+use crate::{MetadataResponse, PtrStorage, Result, ServerTls, Sha256Hash};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// The status hydrus servers respond with when a session key is missing or expired
+const SESSION_EXPIRED: u16 = 419;
+
+/// Serves the mirror on the given address until the server fails
+pub(crate) async fn serve(mirror: Arc<Mirror>, address: SocketAddr) -> Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let mirror = Arc::clone(&mirror);
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let mirror = Arc::clone(&mirror);
+
+                async move { Ok::<_, Infallible>(handle_request(mirror, request).await) }
+            }))
+        }
+    });
+    tracing::info!("serving mirror on {}", address);
+    Server::bind(&address).serve(make_service).await?;
+
+    Ok(())
+}
+
+/// Serves the mirror over https on the given address until the server fails
+pub(crate) async fn serve_tls(
+    mirror: Arc<Mirror>,
+    address: SocketAddr,
+    tls: ServerTls,
+) -> Result<()> {
+    serve_https(address, tls, move |request| {
+        let mirror = Arc::clone(&mirror);
+
+        async move { handle_request(mirror, request).await }
+    })
+    .await
+}
+
+#[tracing::instrument(skip(mirror), level = "debug")]
+async fn handle_request(mirror: Arc<Mirror>, request: Request<Body>) -> Response<Body> {
+    if request.method() != Method::GET {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+    }
+    let access_key = access_key_header(&request);
+    let path = request.uri().path();
+
+    if path == "/session_key" {
+        return match access_key {
+            Some(key) => mirror.sessions.create_response(&key).unwrap_or_else(|e| {
+                tracing::error!("failed to create a session key: {}", e);
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+            }),
+            None => error_response(StatusCode::UNAUTHORIZED, "missing access key"),
+        };
+    }
+    let has_session = session_key_cookie(&request)
+        .map(|k| mirror.sessions.contains(&k))
+        .unwrap_or(false);
+    if path != "/options" && access_key.is_none() && !has_session {
+        return error_response(
+            StatusCode::from_u16(SESSION_EXPIRED).unwrap(),
+            "missing or expired session key",
+        );
+    }
+    let query = request.uri().query().unwrap_or_default();
+
+    let result = match path {
+        "/options" => read_storage(&mirror, PtrStorage::read_options).await,
+        "/account" => read_storage(&mirror, PtrStorage::read_account).await,
+        "/metadata" => match query_param(query, "since").map(|s| s.parse::<u64>()) {
+            Some(Ok(since)) => get_metadata(&mirror, since),
+            _ => return error_response(StatusCode::BAD_REQUEST, "invalid since parameter"),
+        },
+        "/update" => match query_param(query, "update_hash").map(Sha256Hash::from_hex) {
+            Some(Ok(hash)) => read_storage(&mirror, move |s| s.read_update(&hash)).await,
+            _ => return error_response(StatusCode::BAD_REQUEST, "invalid update_hash parameter"),
+        },
+        _ => return error_response(StatusCode::NOT_FOUND, "not found"),
+    };
+
+    match result {
+        Ok(Some(bytes)) => bytes_response(bytes),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "not synced yet"),
+        Err(e) => {
+            tracing::error!("failed to handle request: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+        }
+    }
+}
+
+/// Reads from the storage on the blocking thread pool so that reading
+/// large update files doesn't stall the runtime
+async fn read_storage<F>(mirror: &Arc<Mirror>, read: F) -> Result<Option<Vec<u8>>>
+where
+    F: FnOnce(&PtrStorage) -> Result<Option<Vec<u8>>> + Send + 'static,
+{
+    let mirror = Arc::clone(mirror);
+
+    tokio::task::spawn_blocking(move || read(&mirror.storage))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+fn get_metadata(mirror: &Mirror, since: u64) -> Result<Option<Vec<u8>>> {
+    let metadata = mirror.metadata.read().unwrap();
+
+    metadata
+        .as_ref()
+        .map(|m| encode_body(&MetadataResponse(m.0.since(since))))
+        .transpose()
+}
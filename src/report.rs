@@ -0,0 +1,257 @@
+use crate::{
+    ContentUpdateAction, ContentUpdateResponse, Definitions, StreamedUpdate, UpdateResponse,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// The number of added and deleted mappings of a tag
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TagActivity {
+    pub tag_id: u64,
+    /// The tag if its definition has been seen
+    pub tag: Option<String>,
+    pub added: u64,
+    pub deleted: u64,
+}
+
+/// The number of added and deleted mappings of all tags of a namespace.
+/// Tags without a namespace belong to the empty namespace.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NamespaceActivity {
+    /// The namespace or `None` for tags whose definition hasn't been seen
+    pub namespace: Option<String>,
+    pub added: u64,
+    pub deleted: u64,
+}
+
+/// The number of changes made by the update files of one update index
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct IndexChurn {
+    pub update_index: u64,
+    pub time_begin: u64,
+    pub time_end: u64,
+    pub mappings_added: u64,
+    pub mappings_deleted: u64,
+    pub siblings_added: u64,
+    pub siblings_deleted: u64,
+    pub parents_added: u64,
+    pub parents_deleted: u64,
+}
+
+/// The kind of relationship between two tags
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationshipKind {
+    Sibling,
+    Parent,
+}
+
+/// An added or deleted sibling or parent relationship.
+/// For siblings `tag` is replaced by `other`, for parents
+/// `tag` is the child of `other`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RelationshipChange {
+    pub update_index: u64,
+    pub time_end: u64,
+    pub kind: RelationshipKind,
+    pub action: ContentUpdateAction,
+    pub tag_id: u64,
+    pub tag: Option<String>,
+    pub other_id: u64,
+    pub other: Option<String>,
+}
+
+/// A summary of the changes made by a range of update files
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct UpdateReport {
+    /// The start of the earliest update index
+    pub time_begin: Option<u64>,
+    /// The end of the latest update index
+    pub time_end: Option<u64>,
+    /// The most active tags ordered by their number of changes
+    pub tags: Vec<TagActivity>,
+    /// All namespaces ordered by their number of changes.
+    /// Tags without a known definition are counted in the `None` namespace.
+    pub namespaces: Vec<NamespaceActivity>,
+    /// The changes per update index ordered by the index
+    pub churn: Vec<IndexChurn>,
+    /// All sibling and parent changes in the order they were applied
+    pub relationship_changes: Vec<RelationshipChange>,
+}
+
+/// Collects the changes of streamed update files into an [UpdateReport].
+///
+/// Tags are resolved with the definitions streamed alongside the content.
+/// Definitions of earlier update indices can be added with
+/// [ReportCollector::definitions] so that all tags can be named.
+#[derive(Clone, Debug, Default)]
+pub struct ReportCollector {
+    definitions: Definitions,
+    tags: HashMap<u64, (u64, u64)>,
+    churn: BTreeMap<u64, IndexChurn>,
+    relationship_changes: Vec<RelationshipChange>,
+}
+
+impl ReportCollector {
+    /// Creates a new collector without any changes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the definitions used to resolve tags
+    pub fn definitions(mut self, definitions: Definitions) -> Self {
+        self.definitions = definitions;
+
+        self
+    }
+
+    /// Adds the definitions or changes of a streamed update file
+    pub fn apply(&mut self, streamed: &StreamedUpdate) {
+        match &streamed.update {
+            UpdateResponse::Definitions(definitions) => self.definitions.apply(definitions),
+            UpdateResponse::Content(content) => self.apply_content(streamed, content),
+        }
+    }
+
+    /// Builds the report listing at most `top_tags` of the most active tags
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub fn report(&self, top_tags: usize) -> UpdateReport {
+        let mut tags = self
+            .tags
+            .iter()
+            .map(|(tag_id, (added, deleted))| TagActivity {
+                tag_id: *tag_id,
+                tag: self.tag(*tag_id),
+                added: *added,
+                deleted: *deleted,
+            })
+            .collect::<Vec<_>>();
+        tags.sort_by(|a, b| {
+            (b.added + b.deleted)
+                .cmp(&(a.added + a.deleted))
+                .then_with(|| a.tag.cmp(&b.tag))
+                .then_with(|| a.tag_id.cmp(&b.tag_id))
+        });
+        tags.truncate(top_tags);
+
+        let mut namespaces = BTreeMap::<Option<&str>, (u64, u64)>::new();
+        for (tag_id, (added, deleted)) in &self.tags {
+            let namespace = self
+                .definitions
+                .tag(*tag_id)
+                .map(|tag| tag.split_once(':').map(|(n, _)| n).unwrap_or_default());
+            let activity = namespaces.entry(namespace).or_default();
+            activity.0 += added;
+            activity.1 += deleted;
+        }
+        let mut namespaces = namespaces
+            .into_iter()
+            .map(|(namespace, (added, deleted))| NamespaceActivity {
+                namespace: namespace.map(String::from),
+                added,
+                deleted,
+            })
+            .collect::<Vec<_>>();
+        namespaces.sort_by(|a, b| {
+            (b.added + b.deleted)
+                .cmp(&(a.added + a.deleted))
+                .then_with(|| a.namespace.cmp(&b.namespace))
+        });
+
+        let relationship_changes = self
+            .relationship_changes
+            .iter()
+            .cloned()
+            .map(|mut change| {
+                change.tag = self.tag(change.tag_id);
+                change.other = self.tag(change.other_id);
+                change
+            })
+            .collect();
+
+        UpdateReport {
+            time_begin: self.churn.values().map(|c| c.time_begin).min(),
+            time_end: self.churn.values().map(|c| c.time_end).max(),
+            tags,
+            namespaces,
+            churn: self.churn.values().cloned().collect(),
+            relationship_changes,
+        }
+    }
+
+    fn apply_content(&mut self, streamed: &StreamedUpdate, content: &ContentUpdateResponse) {
+        let churn = self
+            .churn
+            .entry(streamed.update_index)
+            .or_insert_with(|| IndexChurn {
+                update_index: streamed.update_index,
+                time_begin: streamed.time_begin,
+                time_end: streamed.time_end,
+                ..Default::default()
+            });
+
+        for (action, mappings) in &content.mappings {
+            for (tag_id, hash_ids) in mappings {
+                let count = hash_ids.len() as u64;
+                let activity = self.tags.entry(*tag_id).or_default();
+
+                match action {
+                    ContentUpdateAction::Add => activity.0 += count,
+                    ContentUpdateAction::Delete => activity.1 += count,
+                }
+                match action {
+                    ContentUpdateAction::Add => churn.mappings_added += count,
+                    ContentUpdateAction::Delete => churn.mappings_deleted += count,
+                }
+            }
+        }
+        for (kind, relationships) in [
+            (RelationshipKind::Sibling, &content.tag_siblings),
+            (RelationshipKind::Parent, &content.tag_parents),
+        ] {
+            for action in [ContentUpdateAction::Add, ContentUpdateAction::Delete] {
+                let mut pairs = relationships
+                    .get(&action)
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+                pairs.sort_unstable();
+
+                let count = pairs.len() as u64;
+                match (kind, &action) {
+                    (RelationshipKind::Sibling, ContentUpdateAction::Add) => {
+                        churn.siblings_added += count
+                    }
+                    (RelationshipKind::Sibling, ContentUpdateAction::Delete) => {
+                        churn.siblings_deleted += count
+                    }
+                    (RelationshipKind::Parent, ContentUpdateAction::Add) => {
+                        churn.parents_added += count
+                    }
+                    (RelationshipKind::Parent, ContentUpdateAction::Delete) => {
+                        churn.parents_deleted += count
+                    }
+                }
+                self.relationship_changes
+                    .extend(
+                        pairs
+                            .into_iter()
+                            .map(|(tag_id, other_id)| RelationshipChange {
+                                update_index: streamed.update_index,
+                                time_end: streamed.time_end,
+                                kind,
+                                action: action.clone(),
+                                tag_id: *tag_id,
+                                tag: None,
+                                other_id: *other_id,
+                                other: None,
+                            }),
+                    );
+            }
+        }
+    }
+
+    fn tag(&self, tag_id: u64) -> Option<String> {
+        self.definitions.tag(tag_id).map(String::from)
+    }
+}
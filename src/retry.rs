@@ -0,0 +1,169 @@
+use crate::Error;
+use reqwest::StatusCode;
+use std::collections::hash_map::RandomState;
+use std::fmt::{Debug, Formatter};
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+type RetryPredicate = Arc<dyn Fn(&Error) -> bool + Send + Sync>;
+
+/// Describes how often and after which delay failed requests are retried.
+/// The delay doubles with every attempt until it reaches the maximum delay.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retryable: RetryPredicate,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+impl Debug for RetryPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .finish()
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a new policy that makes up to the given number of attempts
+    /// per request and retries errors that are usually transient
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            jitter: true,
+            retryable: Arc::new(Error::is_transient),
+        }
+    }
+
+    /// Creates a policy that never retries requests
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    /// Sets the delay before the first retry
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+
+        self
+    }
+
+    /// Sets the maximum delay between two attempts
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+
+        self
+    }
+
+    /// Randomizes the delay between half and the full computed delay
+    /// so that concurrent clients don't retry at the same time
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+
+        self
+    }
+
+    /// Sets the function that decides whether a failed request is retried
+    pub fn retry_if<F: Fn(&Error) -> bool + Send + Sync + 'static>(mut self, retryable: F) -> Self {
+        self.retryable = Arc::new(retryable);
+
+        self
+    }
+
+    /// Returns the maximum number of attempts per request
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns if a request that failed with the given error
+    /// on the given attempt should be retried
+    pub fn should_retry(&self, attempt: u32, error: &Error) -> bool {
+        attempt < self.max_attempts && (self.retryable)(error)
+    }
+
+    /// Returns the delay before retrying a request that failed with the given error
+    /// on the given attempt or `None` if it shouldn't be retried.
+    /// Exceeded repository bandwidth is waited out until the reset time of the server
+    /// unless the reset is further away than the maximum delay.
+    pub fn retry_delay(&self, attempt: u32, error: &Error) -> Option<Duration> {
+        match error {
+            Error::RepositoryBandwidthExceeded(_, reset_in) => reset_in
+                .filter(|reset_in| attempt < self.max_attempts && *reset_in <= self.max_delay),
+            _ if self.should_retry(attempt, error) => Some(self.delay(attempt)),
+            _ => None,
+        }
+    }
+
+    /// Returns the delay before the attempt following the given one
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+
+        if self.jitter {
+            let half = delay / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            delay
+        }
+    }
+}
+
+impl Error {
+    /// Returns if the error is usually transient like timeouts,
+    /// connection failures and server errors
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Reqwest(e) => e.is_timeout() || e.is_connect(),
+            Error::ResponseStatus(status, _) => is_transient_status(*status),
+            _ => false,
+        }
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+}
+
+/// Returns the delay of the `Retry-After` header in seconds
+pub(crate) fn retry_after(headers: &[(String, String)]) -> Option<Duration> {
+    headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Returns a random number between 0 and 1
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64,
+    );
+
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,323 @@
+mod pending;
+mod service;
+
+use crate::encoding::encode_body;
+use crate::http_server::SessionKeys;
+use crate::server::pending::PendingUpdates;
+use crate::{
+    AccountResponse, BandwidthRules, ClientContent, ClientToServerUpdate, Error, HydrusMetadata,
+    MetadataEntry, MetadataResponse, OptionsResponse, PtrStorage, Result, ServerTls, Sha256Hash,
+    UpdateResponse, UpdateSource,
+};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The default maximum size of a posted update in bytes
+const DEFAULT_MAX_UPDATE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A hydrus tag repository that accepts content from clients, assigns ids
+/// and writes definitions and content update files every update period.
+/// Everything is persisted in a [PtrStorage] so the repository can also be
+/// read by the [crate::FileSystemUpdateSource]. Content that hasn't been
+/// written to update files yet is lost when the server stops.
+pub struct RepositoryServer {
+    storage: PtrStorage,
+    update_period: u64,
+    access_keys: HashSet<String>,
+    allow_anonymous_writes: bool,
+    max_update_size: u64,
+    created: u64,
+    state: Mutex<RepositoryState>,
+    metadata: RwLock<HydrusMetadata>,
+    sessions: SessionKeys,
+}
+
+#[derive(Default)]
+struct RepositoryState {
+    hash_ids: HashMap<Sha256Hash, u64>,
+    tag_ids: HashMap<String, u64>,
+    pending: PendingUpdates,
+}
+
+impl RepositoryServer {
+    /// Opens the repository in the given storage with the given options.
+    /// The ids of hashes and tags are restored from the stored update files.
+    #[tracing::instrument(skip(storage), level = "debug")]
+    pub async fn open(storage: PtrStorage, options: OptionsResponse) -> Result<Self> {
+        storage.write_options(&encode_body(&options)?)?;
+        let metadata = match storage.metadata()? {
+            Some(metadata) => metadata.0,
+            None => HydrusMetadata {
+                entries: Vec::new(),
+                next_update_due: now() + options.update_period,
+            },
+        };
+        let mut state = RepositoryState::default();
+        let source = storage.update_source();
+
+        for hash in metadata.entries.iter().flat_map(|e| e.update_hashes.iter()) {
+            if let UpdateResponse::Definitions(definitions) = source.get_update(hash).await? {
+                state
+                    .hash_ids
+                    .extend(definitions.hashes.into_iter().map(|(id, hash)| (hash, id)));
+                state
+                    .tag_ids
+                    .extend(definitions.tags.into_iter().map(|(id, tag)| (tag, id)));
+            }
+        }
+        tracing::debug!(
+            "restored {} hash and {} tag definitions",
+            state.hash_ids.len(),
+            state.tag_ids.len()
+        );
+
+        Ok(Self {
+            storage,
+            update_period: options.update_period,
+            access_keys: HashSet::new(),
+            allow_anonymous_writes: false,
+            max_update_size: DEFAULT_MAX_UPDATE_SIZE,
+            created: now(),
+            state: Mutex::new(state),
+            metadata: RwLock::new(metadata),
+            sessions: SessionKeys::default(),
+        })
+    }
+
+    /// Allows requests with the given access key
+    pub fn access_key<S: ToString>(mut self, access_key: S) -> Self {
+        self.access_keys.insert(access_key.to_string());
+
+        self
+    }
+
+    /// Allows every request without a valid access key, including posting content.
+    /// The server refuses to serve without access keys unless this is set.
+    pub fn allow_anonymous_writes(mut self, allow: bool) -> Self {
+        self.allow_anonymous_writes = allow;
+
+        self
+    }
+
+    /// Sets the maximum size of a posted update in bytes. Larger updates are rejected.
+    /// The default is 64 MiB.
+    pub fn max_update_size(mut self, max_update_size: u64) -> Self {
+        self.max_update_size = max_update_size;
+
+        self
+    }
+
+    /// Returns the storage of the repository
+    pub fn storage(&self) -> &PtrStorage {
+        &self.storage
+    }
+
+    /// Returns the current metadata of the repository
+    pub fn metadata(&self) -> HydrusMetadata {
+        self.metadata.read().unwrap().clone()
+    }
+
+    /// Assigns ids to new hashes and tags of the update and queues
+    /// its content for the next update files
+    #[tracing::instrument(skip_all, level = "debug")]
+    pub fn process_update(&self, update: &ClientToServerUpdate) {
+        let mut state = self.state.lock().unwrap();
+
+        for change in &update.changes {
+            match &change.content {
+                ClientContent::Mappings { tag, hashes } => {
+                    let tag_id = state.tag_id(tag);
+                    let hash_ids = hashes.iter().map(|h| state.hash_id(h)).collect::<Vec<_>>();
+                    state
+                        .pending
+                        .change_mappings(change.action.clone(), tag_id, &hash_ids);
+                }
+                ClientContent::TagSibling { tag, sibling } => {
+                    let tag_id = state.tag_id(tag);
+                    let sibling_id = state.tag_id(sibling);
+                    state
+                        .pending
+                        .change_tag_sibling(change.action.clone(), tag_id, sibling_id);
+                }
+                ClientContent::TagParent { child, parent } => {
+                    let child_id = state.tag_id(child);
+                    let parent_id = state.tag_id(parent);
+                    state
+                        .pending
+                        .change_tag_parent(change.action.clone(), child_id, parent_id);
+                }
+            }
+        }
+    }
+
+    /// Writes the queued definitions and content into update files
+    /// and adds them to the metadata as a new update index.
+    /// If nothing is queued no update index is added and
+    /// only the next update is scheduled.
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub fn publish_updates(&self) -> Result<Option<MetadataEntry>> {
+        let mut state = self.state.lock().unwrap();
+        let mut metadata = self.metadata.read().unwrap().clone();
+
+        if state.pending.is_empty() {
+            metadata.next_update_due = now() + self.update_period;
+            self.storage
+                .write_metadata(&encode_body(&MetadataResponse(metadata.clone()))?)?;
+            *self.metadata.write().unwrap() = metadata;
+            tracing::debug!("nothing to publish");
+
+            return Ok(None);
+        }
+        let files = state.pending.clone().into_update_files()?;
+
+        for file in &files {
+            self.storage.write_update(&file.hash, &file.bytes)?;
+        }
+        let time_end = now();
+        let entry = MetadataEntry {
+            update_index: metadata
+                .latest_update_index()
+                .map(|i| i + 1)
+                .unwrap_or_default(),
+            update_hashes: files.into_iter().map(|file| file.hash).collect(),
+            time_begin: metadata
+                .entries
+                .last()
+                .map(|e| e.time_end)
+                .unwrap_or_else(|| metadata.next_update_due.saturating_sub(self.update_period)),
+            time_end,
+        };
+        metadata.entries.push(entry.clone());
+        metadata.next_update_due = time_end + self.update_period;
+        self.storage
+            .write_metadata(&encode_body(&MetadataResponse(metadata.clone()))?)?;
+        *self.metadata.write().unwrap() = metadata;
+        state.pending = PendingUpdates::default();
+        tracing::info!(
+            "published update index {} with {} files",
+            entry.update_index,
+            entry.update_hashes.len()
+        );
+
+        Ok(Some(entry))
+    }
+
+    /// Returns the duration until the next update files are due
+    pub fn next_update_in(&self) -> Duration {
+        let next_update_due = self.metadata.read().unwrap().next_update_due;
+
+        Duration::from_secs(next_update_due.saturating_sub(now()))
+    }
+
+    /// Publishes update files whenever they are due
+    pub async fn publish_forever(&self) {
+        loop {
+            let delay = self.next_update_in();
+            tracing::debug!("next update in {:?}", delay);
+            tokio::time::sleep(delay).await;
+
+            if let Err(e) = self.publish_updates() {
+                tracing::error!("failed to publish updates: {}", e);
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+        }
+    }
+
+    /// Serves the repository over plain http on the given address. Fails if no access
+    /// key is configured and anonymous writes haven't been allowed explicitly.
+    /// Hydrus clients only connect over https, see [RepositoryServer::serve_tls].
+    pub async fn serve(self: Arc<Self>, address: SocketAddr) -> Result<()> {
+        self.check_access()?;
+
+        service::serve(self, address).await
+    }
+
+    /// Serves the repository over https on the given address. Fails if no access
+    /// key is configured and anonymous writes haven't been allowed explicitly.
+    pub async fn serve_tls(self: Arc<Self>, address: SocketAddr, tls: ServerTls) -> Result<()> {
+        self.check_access()?;
+
+        service::serve_tls(self, address, tls).await
+    }
+
+    fn check_access(&self) -> Result<()> {
+        if self.access_keys.is_empty() && !self.allow_anonymous_writes {
+            return Err(Error::Builder(String::from(
+                "no access keys configured and anonymous writes aren't allowed",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the account of the given access key. Accounts are identified by the
+    /// hash of their access key and don't have bandwidth rules.
+    pub(crate) fn account(&self, access_key: Option<&str>) -> AccountResponse {
+        let (account_key, account_type) = match access_key {
+            Some(key) if self.access_keys.contains(key) => {
+                (Sha256Hash::digest(key.as_bytes()).to_hex(), "user")
+            }
+            _ => (String::new(), "anonymous"),
+        };
+
+        AccountResponse {
+            account_key,
+            account_type: String::from(account_type),
+            created: self.created,
+            expires: None,
+            bandwidth_rules: BandwidthRules::new(),
+        }
+    }
+
+    /// Returns if a request with the given access key or session key is allowed
+    pub(crate) fn is_authorized(
+        &self,
+        access_key: Option<&str>,
+        session_key: Option<&str>,
+    ) -> bool {
+        if self.allow_anonymous_writes || access_key.map(|k| self.is_access_key(k)) == Some(true) {
+            return true;
+        }
+        session_key
+            .map(|k| self.sessions.contains(k))
+            .unwrap_or(false)
+    }
+
+    /// Returns if the given access key is allowed
+    pub(crate) fn is_access_key(&self, access_key: &str) -> bool {
+        self.allow_anonymous_writes || self.access_keys.contains(access_key)
+    }
+}
+
+impl RepositoryState {
+    fn hash_id(&mut self, hash: &Sha256Hash) -> u64 {
+        if let Some(id) = self.hash_ids.get(hash) {
+            return *id;
+        }
+        let id = self.hash_ids.len() as u64 + 1;
+        self.hash_ids.insert(*hash, id);
+        self.pending.define_hash(id, *hash);
+
+        id
+    }
+
+    fn tag_id(&mut self, tag: &str) -> u64 {
+        if let Some(id) = self.tag_ids.get(tag) {
+            return *id;
+        }
+        let id = self.tag_ids.len() as u64 + 1;
+        self.tag_ids.insert(tag.to_string(), id);
+        self.pending.define_tag(id, tag.to_string());
+
+        id
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
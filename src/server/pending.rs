@@ -0,0 +1,64 @@
+use crate::{
+    ContentUpdateAction, ContentUpdateBuilder, DefinitionsUpdateBuilder, Result, Sha256Hash,
+    UpdateFile,
+};
+use std::mem::take;
+
+/// Definitions and content received since the last update files were written
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PendingUpdates {
+    definitions: DefinitionsUpdateBuilder,
+    content: ContentUpdateBuilder,
+}
+
+impl PendingUpdates {
+    pub fn define_hash(&mut self, id: u64, hash: Sha256Hash) {
+        self.definitions = take(&mut self.definitions).hash(id, hash);
+    }
+
+    pub fn define_tag(&mut self, id: u64, tag: String) {
+        self.definitions = take(&mut self.definitions).tag(id, tag);
+    }
+
+    /// Adds or deletes mappings. A change cancels a pending opposite change.
+    pub fn change_mappings(&mut self, action: ContentUpdateAction, tag_id: u64, hash_ids: &[u64]) {
+        self.content = take(&mut self.content).mappings(action, tag_id, hash_ids.iter().copied());
+    }
+
+    pub fn change_tag_sibling(
+        &mut self,
+        action: ContentUpdateAction,
+        tag_id: u64,
+        sibling_id: u64,
+    ) {
+        self.content = take(&mut self.content).tag_sibling(action, tag_id, sibling_id);
+    }
+
+    pub fn change_tag_parent(
+        &mut self,
+        action: ContentUpdateAction,
+        child_id: u64,
+        parent_id: u64,
+    ) {
+        self.content = take(&mut self.content).tag_parent(action, child_id, parent_id);
+    }
+
+    /// Returns if neither definitions nor content are pending
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty() && self.content.is_empty()
+    }
+
+    /// Encodes the pending definitions and content into update files
+    pub fn into_update_files(self) -> Result<Vec<UpdateFile>> {
+        let mut files = Vec::new();
+
+        if !self.definitions.is_empty() {
+            files.push(self.definitions.build()?);
+        }
+        if !self.content.is_empty() {
+            files.push(self.content.build()?);
+        }
+
+        Ok(files)
+    }
+}
@@ -0,0 +1,161 @@
+use crate::encoding::{decode_body, encode_body};
+use crate::http_server::{
+    access_key_header, bytes_response, error_response, query_param, serve_tls as serve_https,
+    session_key_cookie,
+};
+use crate::server::RepositoryServer;
+use crate::{ClientToServerUpdate, MetadataResponse, Result, ServerTls, Sha256Hash};
+use hyper::body::HttpBody;
+use hyper::header::CONTENT_LENGTH;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Serves the repository on the given address until the server fails
+pub(crate) async fn serve(server: Arc<RepositoryServer>, address: SocketAddr) -> Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let server = Arc::clone(&server);
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let server = Arc::clone(&server);
+
+                async move { Ok::<_, Infallible>(handle_request(&server, request).await) }
+            }))
+        }
+    });
+    tracing::info!("serving repository on {}", address);
+    Server::bind(&address).serve(make_service).await?;
+
+    Ok(())
+}
+
+/// Serves the repository over https on the given address until the server fails
+pub(crate) async fn serve_tls(
+    server: Arc<RepositoryServer>,
+    address: SocketAddr,
+    tls: ServerTls,
+) -> Result<()> {
+    serve_https(address, tls, move |request| {
+        let server = Arc::clone(&server);
+
+        async move { handle_request(&server, request).await }
+    })
+    .await
+}
+
+#[tracing::instrument(skip(server), level = "debug")]
+async fn handle_request(server: &RepositoryServer, request: Request<Body>) -> Response<Body> {
+    let access_key = access_key_header(&request);
+    let session_key = session_key_cookie(&request);
+    let path = request.uri().path().to_string();
+
+    if path == "/session_key" {
+        return match access_key {
+            Some(key) if server.is_access_key(&key) => {
+                result_response(server.sessions.create_response(&key))
+            }
+            Some(_) => error_response(StatusCode::FORBIDDEN, "invalid access key"),
+            None => error_response(StatusCode::UNAUTHORIZED, "missing access key"),
+        };
+    }
+    if path != "/options" && !server.is_authorized(access_key.as_deref(), session_key.as_deref()) {
+        return error_response(StatusCode::FORBIDDEN, "invalid access or session key");
+    }
+    let query = request.uri().query().unwrap_or_default().to_string();
+
+    let result = match (request.method(), path.as_str()) {
+        (&Method::GET, "/options") => server.storage.read_options(),
+        (&Method::GET, "/account") => {
+            let access_key =
+                access_key.or_else(|| session_key.and_then(|k| server.sessions.access_key(&k)));
+            encode_body(&server.account(access_key.as_deref())).map(Some)
+        }
+        (&Method::GET, "/metadata") => match query_param(&query, "since").map(|s| s.parse::<u64>())
+        {
+            Some(Ok(since)) => {
+                encode_body(&MetadataResponse(server.metadata().since(since))).map(Some)
+            }
+            _ => return error_response(StatusCode::BAD_REQUEST, "invalid since parameter"),
+        },
+        (&Method::GET, "/update") => {
+            match query_param(&query, "update_hash").map(Sha256Hash::from_hex) {
+                Some(Ok(hash)) => server.storage.read_update(&hash),
+                _ => {
+                    return error_response(StatusCode::BAD_REQUEST, "invalid update_hash parameter")
+                }
+            }
+        }
+        (&Method::POST, "/update") => {
+            let body = match read_body(request, server.max_update_size).await {
+                Ok(Some(body)) => body,
+                Ok(None) => {
+                    return error_response(StatusCode::PAYLOAD_TOO_LARGE, "update is too large")
+                }
+                Err(e) => {
+                    tracing::debug!("failed to read update: {}", e);
+                    return error_response(StatusCode::BAD_REQUEST, "invalid request body");
+                }
+            };
+            match decode_body::<ClientToServerUpdate>(body) {
+                Ok(update) => {
+                    server.process_update(&update);
+                    return Response::new(Body::empty());
+                }
+                Err(e) => {
+                    tracing::debug!("received invalid update: {}", e);
+                    return error_response(
+                        StatusCode::BAD_REQUEST,
+                        "invalid client to server update",
+                    );
+                }
+            }
+        }
+        (_, "/options" | "/account" | "/metadata" | "/update") => {
+            return error_response(StatusCode::METHOD_NOT_ALLOWED, "method not allowed")
+        }
+        _ => return error_response(StatusCode::NOT_FOUND, "not found"),
+    };
+
+    match result {
+        Ok(Some(bytes)) => bytes_response(bytes),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "not found"),
+        Err(e) => {
+            tracing::error!("failed to handle request: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+        }
+    }
+}
+
+/// Reads the request body or returns `None` if it is larger than the given size
+async fn read_body(request: Request<Body>, max_size: u64) -> Result<Option<Vec<u8>>> {
+    let content_length = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if content_length.is_some_and(|length| length > max_size) {
+        return Ok(None);
+    }
+    let mut body = request.into_body();
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if (bytes.len() + chunk.len()) as u64 > max_size {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(Some(bytes))
+}
+
+fn result_response(result: Result<Response<Body>>) -> Response<Body> {
+    result.unwrap_or_else(|e| {
+        tracing::error!("failed to handle request: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+    })
+}
@@ -0,0 +1,176 @@
+use crate::encoding::{decode_body, encode_body};
+use crate::{
+    Client, Error, FileSystemUpdateSource, HydrusMetadata, MetadataResponse, OptionsEndpoint,
+    OptionsResponse, Result, Sha256Hash, UpdateSource,
+};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+const OPTIONS_FILE: &str = "options";
+const METADATA_FILE: &str = "metadata";
+const ACCOUNT_FILE: &str = "account";
+const UPDATES_DIR: &str = "updates";
+
+/// A directory storing the raw options, metadata and update files of a PTR.
+/// Update files are stored in the `updates` subdirectory by hash so that
+/// it can be read with the [FileSystemUpdateSource].
+#[derive(Clone, Debug)]
+pub struct PtrStorage {
+    root: PathBuf,
+}
+
+/// The result of a sync with the PTR
+#[derive(Clone, Debug)]
+pub struct SyncSummary {
+    pub total_updates: usize,
+    pub downloaded_updates: usize,
+}
+
+impl PtrStorage {
+    /// Opens the storage in the given directory and creates it if it doesn't exist
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(root.join(UPDATES_DIR))?;
+
+        Ok(Self { root })
+    }
+
+    /// Returns an update source reading the stored update files
+    pub fn update_source(&self) -> FileSystemUpdateSource {
+        FileSystemUpdateSource::new(self.root.join(UPDATES_DIR))
+    }
+
+    /// Fetches the options and the metadata since the last stored update index
+    /// of the PTR the client is connected to and downloads all update files
+    /// that aren't stored yet
+    #[tracing::instrument(skip(client), level = "debug")]
+    pub async fn sync(&self, client: &Client) -> Result<SyncSummary> {
+        let options = client.get_raw::<OptionsEndpoint, _>(&()).await?;
+        self.write_options(&options)?;
+
+        let mut entries = self.metadata()?.map(|m| m.0.entries).unwrap_or_default();
+        let since = entries
+            .iter()
+            .map(|e| e.update_index + 1)
+            .max()
+            .unwrap_or_default();
+        let new_metadata = client.get_metadata(since).await?.0;
+        let new_entries = new_metadata
+            .entries
+            .into_iter()
+            .filter(|e| e.update_index >= since)
+            .collect::<Vec<_>>();
+        let mut downloaded_updates = 0;
+
+        for hash in new_entries.iter().flat_map(|e| e.update_hashes.iter()) {
+            if self.has_update(hash) {
+                continue;
+            }
+            tracing::debug!("downloading update {}", hash);
+            let bytes = client.get_raw_update(hash).await?;
+            self.write_update(hash, &bytes)?;
+            downloaded_updates += 1;
+        }
+        entries.extend(new_entries);
+        let metadata = MetadataResponse(HydrusMetadata {
+            entries,
+            next_update_due: new_metadata.next_update_due,
+        });
+        let total_updates = metadata.update_hashes().len();
+
+        // the metadata is only stored after all updates it references are available
+        self.write_metadata(&encode_body(&metadata)?)?;
+        tracing::info!(
+            "synced {} new of {} total updates",
+            downloaded_updates,
+            total_updates
+        );
+
+        Ok(SyncSummary {
+            total_updates,
+            downloaded_updates,
+        })
+    }
+
+    /// Returns the stored options
+    pub fn options(&self) -> Result<Option<OptionsResponse>> {
+        self.read_options()?.map(decode_body).transpose()
+    }
+
+    /// Returns the stored metadata
+    pub fn metadata(&self) -> Result<Option<MetadataResponse>> {
+        self.read_metadata()?.map(decode_body).transpose()
+    }
+
+    /// Returns the raw stored options
+    pub fn read_options(&self) -> Result<Option<Vec<u8>>> {
+        Self::read_optional(&self.root.join(OPTIONS_FILE))
+    }
+
+    /// Stores the raw options
+    pub fn write_options(&self, bytes: &[u8]) -> Result<()> {
+        Self::write_atomic(&self.root.join(OPTIONS_FILE), bytes)
+    }
+
+    /// Returns the raw stored metadata
+    pub fn read_metadata(&self) -> Result<Option<Vec<u8>>> {
+        Self::read_optional(&self.root.join(METADATA_FILE))
+    }
+
+    /// Stores the raw metadata
+    pub fn write_metadata(&self, bytes: &[u8]) -> Result<()> {
+        Self::write_atomic(&self.root.join(METADATA_FILE), bytes)
+    }
+
+    /// Returns the raw stored account
+    pub fn read_account(&self) -> Result<Option<Vec<u8>>> {
+        Self::read_optional(&self.root.join(ACCOUNT_FILE))
+    }
+
+    /// Stores the raw account
+    pub fn write_account(&self, bytes: &[u8]) -> Result<()> {
+        Self::write_atomic(&self.root.join(ACCOUNT_FILE), bytes)
+    }
+
+    /// Returns the raw update file with the given hash
+    pub fn read_update(&self, hash: &Sha256Hash) -> Result<Option<Vec<u8>>> {
+        Self::read_optional(&self.update_path(hash))
+    }
+
+    /// Returns if the update file with the given hash is stored
+    pub fn has_update(&self, hash: &Sha256Hash) -> bool {
+        self.update_path(hash).is_file()
+    }
+
+    /// Stores the raw update file with the given hash.
+    /// Files that don't match their hash are rejected.
+    pub fn write_update(&self, hash: &Sha256Hash, bytes: &[u8]) -> Result<()> {
+        let actual_hash = Sha256Hash::digest(bytes);
+
+        if actual_hash != *hash {
+            return Err(Error::HashMismatch(*hash, actual_hash));
+        }
+        Self::write_atomic(&self.update_path(hash), bytes)
+    }
+
+    fn update_path(&self, hash: &Sha256Hash) -> PathBuf {
+        self.root.join(UPDATES_DIR).join(hash.to_hex())
+    }
+
+    fn read_optional(path: &Path) -> Result<Option<Vec<u8>>> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, path)?;
+
+        Ok(())
+    }
+}
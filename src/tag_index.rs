@@ -0,0 +1,225 @@
+use crate::tag_siblings::{remove_from, TagSiblings};
+use crate::{
+    ContentUpdateAction, ContentUpdateResponse, Definitions, DefinitionsUpdateResponse, Sha256Hash,
+    UpdateResponse,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// How tags are displayed by the queries of a [TagIndex]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TagDisplay {
+    /// The tags as they are stored without any relationships applied
+    #[default]
+    Storage,
+    /// Tags are replaced by their ideal sibling
+    Siblings,
+    /// Tags are replaced by their ideal sibling and all their
+    /// parents are added
+    SiblingsAndParents,
+}
+
+/// The current mappings, siblings and parents accumulated from update files.
+/// Updates have to be applied in order, e.g. by streaming them with
+/// [crate::StreamOrdering::DefinitionsFirst], so that deletions
+/// override earlier additions.
+#[derive(Clone, Debug, Default)]
+pub struct TagIndex {
+    definitions: Definitions,
+    hash_ids: HashMap<Sha256Hash, u64>,
+    tag_ids: HashMap<String, u64>,
+    files_by_tag: HashMap<u64, HashSet<u64>>,
+    tags_by_file: HashMap<u64, HashSet<u64>>,
+    siblings: TagSiblings,
+    parents: HashMap<u64, HashSet<u64>>,
+    children: HashMap<u64, HashSet<u64>>,
+}
+
+impl TagIndex {
+    /// Creates a new empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the definitions applied to the index
+    pub fn definitions(&self) -> &Definitions {
+        &self.definitions
+    }
+
+    /// Applies a definitions or content update
+    pub fn apply(&mut self, update: &UpdateResponse) {
+        match update {
+            UpdateResponse::Definitions(definitions) => self.apply_definitions(definitions),
+            UpdateResponse::Content(content) => self.apply_content(content),
+        }
+    }
+
+    /// Adds the hashes and tags defined in the given update
+    pub fn apply_definitions(&mut self, update: &DefinitionsUpdateResponse) {
+        self.definitions.apply(update);
+        self.hash_ids
+            .extend(update.hashes.iter().map(|(id, hash)| (*hash, *id)));
+        self.tag_ids
+            .extend(update.tags.iter().map(|(id, tag)| (tag.clone(), *id)));
+    }
+
+    /// Applies the mappings, siblings and parents of the given update.
+    /// Additions are applied before deletions.
+    #[tracing::instrument(skip_all, level = "trace")]
+    pub fn apply_content(&mut self, update: &ContentUpdateResponse) {
+        for action in [ContentUpdateAction::Add, ContentUpdateAction::Delete] {
+            for (tag_id, hash_ids) in update.mappings.get(&action).into_iter().flatten() {
+                for hash_id in hash_ids {
+                    self.change_mapping(&action, *tag_id, *hash_id);
+                }
+            }
+            for (tag_id, sibling_id) in update.tag_siblings.get(&action).into_iter().flatten() {
+                self.siblings.change(&action, *tag_id, *sibling_id);
+            }
+            for (child_id, parent_id) in update.tag_parents.get(&action).into_iter().flatten() {
+                self.change_parent(&action, *child_id, *parent_id);
+            }
+        }
+    }
+
+    /// Returns the sorted tags of the file with the given hash
+    pub fn tags_for_hash(&self, hash: &Sha256Hash, display: TagDisplay) -> Vec<&str> {
+        let tag_ids = self
+            .hash_ids
+            .get(hash)
+            .and_then(|id| self.tags_by_file.get(id))
+            .into_iter()
+            .flatten()
+            .copied();
+        let mut tags = self
+            .display_tag_ids(tag_ids, display)
+            .into_iter()
+            .filter_map(|id| self.definitions.tag(id))
+            .collect::<Vec<_>>();
+        tags.sort_unstable();
+
+        tags
+    }
+
+    /// Returns the hashes of all files that have the given tag
+    pub fn hashes_for_tag(&self, tag: &str, display: TagDisplay) -> Vec<&Sha256Hash> {
+        self.hash_ids_for_tag(tag, display)
+            .into_iter()
+            .filter_map(|id| self.definitions.hash(id))
+            .collect()
+    }
+
+    /// Returns the number of files that have the given tag
+    pub fn count_for_tag(&self, tag: &str, display: TagDisplay) -> usize {
+        self.hash_ids_for_tag(tag, display).len()
+    }
+
+    /// Returns the ideal sibling of the given tag
+    pub fn ideal_sibling(&self, tag: &str) -> Option<&str> {
+        let tag_id = self.tag_ids.get(tag)?;
+
+        self.definitions.tag(self.siblings.ideal(*tag_id))
+    }
+
+    fn change_mapping(&mut self, action: &ContentUpdateAction, tag_id: u64, hash_id: u64) {
+        match action {
+            ContentUpdateAction::Add => {
+                self.files_by_tag.entry(tag_id).or_default().insert(hash_id);
+                self.tags_by_file.entry(hash_id).or_default().insert(tag_id);
+            }
+            ContentUpdateAction::Delete => {
+                if let Some(hash_ids) = self.files_by_tag.get_mut(&tag_id) {
+                    hash_ids.remove(&hash_id);
+                }
+                if let Some(tag_ids) = self.tags_by_file.get_mut(&hash_id) {
+                    tag_ids.remove(&tag_id);
+                }
+            }
+        }
+    }
+
+    fn change_parent(&mut self, action: &ContentUpdateAction, child_id: u64, parent_id: u64) {
+        match action {
+            ContentUpdateAction::Add => {
+                self.parents.entry(child_id).or_default().insert(parent_id);
+                self.children.entry(parent_id).or_default().insert(child_id);
+            }
+            ContentUpdateAction::Delete => {
+                remove_from(&mut self.parents, child_id, parent_id);
+                remove_from(&mut self.children, parent_id, child_id);
+            }
+        }
+    }
+
+    fn hash_ids_for_tag(&self, tag: &str, display: TagDisplay) -> HashSet<u64> {
+        let tag_id = match self.tag_ids.get(tag) {
+            Some(id) => *id,
+            None => return HashSet::new(),
+        };
+        let mut stored_ids = HashSet::new();
+
+        if display == TagDisplay::Storage {
+            stored_ids.insert(tag_id);
+        } else {
+            let ideal_id = self.siblings.ideal(tag_id);
+            let mut display_ids = HashSet::from([ideal_id]);
+            let mut queue = vec![ideal_id];
+
+            while let Some(id) = queue.pop() {
+                for stored_id in self.siblings.group(id) {
+                    stored_ids.insert(stored_id);
+                    if display != TagDisplay::SiblingsAndParents {
+                        continue;
+                    }
+                    for child_id in self.children.get(&stored_id).into_iter().flatten() {
+                        let ideal_child_id = self.siblings.ideal(*child_id);
+                        if display_ids.insert(ideal_child_id) {
+                            queue.push(ideal_child_id);
+                        }
+                    }
+                }
+            }
+        }
+
+        stored_ids
+            .iter()
+            .filter_map(|id| self.files_by_tag.get(id))
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// Applies siblings and parents to the given stored tags
+    fn display_tag_ids<I: IntoIterator<Item = u64>>(
+        &self,
+        tag_ids: I,
+        display: TagDisplay,
+    ) -> BTreeSet<u64> {
+        match display {
+            TagDisplay::Storage => tag_ids.into_iter().collect(),
+            TagDisplay::Siblings => tag_ids
+                .into_iter()
+                .map(|id| self.siblings.ideal(id))
+                .collect(),
+            TagDisplay::SiblingsAndParents => {
+                let mut display_ids = BTreeSet::new();
+                let mut queue = tag_ids
+                    .into_iter()
+                    .map(|id| self.siblings.ideal(id))
+                    .collect::<Vec<_>>();
+
+                while let Some(id) = queue.pop() {
+                    if !display_ids.insert(id) {
+                        continue;
+                    }
+                    for stored_id in self.siblings.group(id) {
+                        for parent_id in self.parents.get(&stored_id).into_iter().flatten() {
+                            queue.push(self.siblings.ideal(*parent_id));
+                        }
+                    }
+                }
+
+                display_ids
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::ContentUpdateAction;
+use std::collections::{HashMap, HashSet};
+
+/// The current tag siblings with a reverse map to find the tags replaced by a tag
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TagSiblings {
+    siblings: HashMap<u64, u64>,
+    /// The tags that are replaced by a tag
+    sibling_sources: HashMap<u64, HashSet<u64>>,
+}
+
+impl TagSiblings {
+    /// Adds or deletes the sibling of a tag. A tag only has one sibling,
+    /// so adding a sibling replaces the previous one.
+    pub fn change(&mut self, action: &ContentUpdateAction, tag_id: u64, sibling_id: u64) {
+        match action {
+            ContentUpdateAction::Add => {
+                if let Some(previous_id) = self.siblings.insert(tag_id, sibling_id) {
+                    remove_from(&mut self.sibling_sources, previous_id, tag_id);
+                }
+                self.sibling_sources
+                    .entry(sibling_id)
+                    .or_default()
+                    .insert(tag_id);
+            }
+            ContentUpdateAction::Delete => {
+                if self.siblings.get(&tag_id) == Some(&sibling_id) {
+                    self.siblings.remove(&tag_id);
+                    remove_from(&mut self.sibling_sources, sibling_id, tag_id);
+                }
+            }
+        }
+    }
+
+    /// Returns the stored tags that are displayed as the given ideal tag
+    pub fn group(&self, ideal_id: u64) -> Vec<u64> {
+        if !self.sibling_sources.contains_key(&ideal_id) {
+            return if self.ideal(ideal_id) == ideal_id {
+                vec![ideal_id]
+            } else {
+                Vec::new()
+            };
+        }
+        let mut visited = HashSet::from([ideal_id]);
+        let mut queue = vec![ideal_id];
+        let mut group = Vec::new();
+
+        while let Some(id) = queue.pop() {
+            if self.ideal(id) == ideal_id {
+                group.push(id);
+            }
+            for source_id in self.sibling_sources.get(&id).into_iter().flatten() {
+                if visited.insert(*source_id) {
+                    queue.push(*source_id);
+                }
+            }
+        }
+
+        group
+    }
+
+    /// Follows the sibling chain of the tag until its end or a loop
+    pub fn ideal(&self, tag_id: u64) -> u64 {
+        let mut visited = HashSet::new();
+        let mut current = tag_id;
+
+        while let Some(sibling_id) = self.siblings.get(&current) {
+            if !visited.insert(current) {
+                break;
+            }
+            current = *sibling_id;
+        }
+
+        current
+    }
+}
+
+/// Removes the value from the set of the key and drops the set once it is empty
+pub(crate) fn remove_from(map: &mut HashMap<u64, HashSet<u64>>, key: u64, value: u64) {
+    if let Some(values) = map.get_mut(&key) {
+        values.remove(&value);
+        if values.is_empty() {
+            map.remove(&key);
+        }
+    }
+}
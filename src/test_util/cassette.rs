@@ -0,0 +1,280 @@
+use crate::{Error, HttpMethod, HttpRequest, HttpResponse, HttpTransport, Result, TransportFuture};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const REDACTED_HEADERS: &[&str] = &["hydrus-key", "cookie", "set-cookie"];
+const REDACTED: &str = "<redacted>";
+
+/// A list of recorded http exchanges that can be replayed
+/// with a [ReplayTransport]. Cassette files contain one json
+/// encoded interaction per line.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+/// A single recorded request and the response it received
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// A recorded request. The access key and cookie headers are redacted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// The base64 encoded body
+    pub body: Option<String>,
+}
+
+/// A recorded response with its compressed body. Session cookies are redacted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The base64 encoded body
+    pub body: String,
+}
+
+impl Cassette {
+    /// Loads a cassette from the given file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let interactions = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<_>>()?;
+
+        Ok(Self { interactions })
+    }
+
+    /// Writes the cassette to the given file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut file = create_cassette_file(path)?;
+
+        for interaction in &self.interactions {
+            interaction.write_line(&mut file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of recorded interactions
+    pub fn len(&self) -> usize {
+        self.interactions.len()
+    }
+
+    /// Returns if the cassette doesn't contain any interactions
+    pub fn is_empty(&self) -> bool {
+        self.interactions.is_empty()
+    }
+}
+
+impl Interaction {
+    fn record(request: &HttpRequest, response: &HttpResponse) -> Self {
+        Self {
+            request: RecordedRequest {
+                method: method_name(request.method).to_string(),
+                url: request.url.clone(),
+                headers: redact(&request.headers),
+                body: request.body.as_ref().map(|body| BASE64.encode(body)),
+            },
+            response: RecordedResponse {
+                status: response.status.as_u16(),
+                headers: redact(&response.headers),
+                body: BASE64.encode(&response.body),
+            },
+        }
+    }
+
+    fn write_line<W: Write>(&self, writer: &mut W) -> Result<()> {
+        serde_json::to_writer(&mut *writer, self)?;
+        writer.write_all(b"\n")?;
+
+        Ok(())
+    }
+
+    fn response(&self) -> Result<HttpResponse> {
+        let status = StatusCode::from_u16(self.response.status).map_err(|_| Error::Malformed)?;
+        let body = BASE64
+            .decode(&self.response.body)
+            .map_err(|_| Error::Malformed)?;
+
+        Ok(HttpResponse {
+            status,
+            headers: self.response.headers.clone(),
+            body,
+        })
+    }
+}
+
+/// A transport that sends requests with an inner transport and
+/// appends every exchange to a cassette file
+pub struct RecordingTransport<T: HttpTransport> {
+    inner: T,
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+impl<T: HttpTransport> RecordingTransport<T> {
+    /// Creates a new recording transport writing to the given path.
+    /// An existing cassette at the path is overwritten with the first request.
+    pub fn new<P: AsRef<Path>>(inner: T, path: P) -> Self {
+        Self {
+            inner,
+            path: path.as_ref().to_path_buf(),
+            file: Mutex::default(),
+        }
+    }
+
+    /// Loads the interactions recorded so far
+    pub fn cassette(&self) -> Result<Cassette> {
+        Cassette::load(&self.path)
+    }
+}
+
+impl<T: HttpTransport> HttpTransport for RecordingTransport<T> {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let response = self.inner.send(request.clone()).await?;
+            let mut file = self.file.lock().unwrap();
+
+            if file.is_none() {
+                *file = Some(create_cassette_file(&self.path)?);
+            }
+            if let Some(file) = file.as_mut() {
+                Interaction::record(&request, &response).write_line(file)?;
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+/// A transport that answers requests with the responses of a [Cassette].
+///
+/// Requests are matched by their method, path and query so that a cassette
+/// recorded against a live repository can be replayed with any endpoint.
+/// Each interaction is played once before it is reused for repeated requests.
+pub struct ReplayTransport {
+    interactions: Vec<(String, Interaction)>,
+    played: Mutex<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    /// Creates a new transport replaying the given cassette
+    pub fn new(cassette: Cassette) -> Self {
+        let interactions = cassette
+            .interactions
+            .into_iter()
+            .map(|interaction| {
+                let key = format!(
+                    "{} {}",
+                    interaction.request.method,
+                    request_target(&interaction.request.url)
+                );
+                (key, interaction)
+            })
+            .collect::<Vec<_>>();
+        let played = Mutex::new(vec![false; interactions.len()]);
+
+        Self {
+            interactions,
+            played,
+        }
+    }
+
+    /// Loads the cassette at the given path for replay
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+
+    fn find(&self, key: &str) -> Option<&Interaction> {
+        let mut played = self.played.lock().unwrap();
+        let mut matches = self
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, (k, _))| k == key)
+            .peekable();
+        let first = matches.peek().map(|(i, _)| *i)?;
+        let index = matches
+            .find(|(i, _)| !played[*i])
+            .map(|(i, _)| i)
+            .unwrap_or(first);
+        played[index] = true;
+
+        Some(&self.interactions[index].1)
+    }
+}
+
+impl HttpTransport for ReplayTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let key = format!(
+                "{} {}",
+                method_name(request.method),
+                request_target(&request.url)
+            );
+
+            match self.find(&key) {
+                Some(interaction) => interaction.response(),
+                None => Err(Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("no recorded interaction for {}", key),
+                ))),
+            }
+        })
+    }
+}
+
+/// Creates or truncates the cassette file and its parent directories
+fn create_cassette_file<P: AsRef<Path>>(path: P) -> Result<File> {
+    if let Some(parent) = path.as_ref().parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    Ok(File::create(path)?)
+}
+
+/// Replaces the values of headers with access keys or session keys
+fn redact(headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            if REDACTED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                (name.clone(), String::from(REDACTED))
+            } else {
+                (name.clone(), value.clone())
+            }
+        })
+        .collect()
+}
+
+/// Returns the path and query of the url without the host
+fn request_target(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+fn method_name(method: HttpMethod) -> &'static str {
+    match method {
+        HttpMethod::Get => "GET",
+        HttpMethod::Post => "POST",
+    }
+}
@@ -0,0 +1,257 @@
+//! Utilities for testing code that talks to a hydrus repository without network access.
+//!
+//! ```
+//! # use hydrus_ptr_client::test_util::FakeServer;
+//! # async fn a() {
+//! let server = FakeServer::builder()
+//!     .update_json(
+//!         "4a4d13c1fcdf0cf734927ec4c9637fdac6144512ad7dc919e0f222e7b0e71586".parse().unwrap(),
+//!         serde_json::json!([36, 1, []]),
+//!     )
+//!     .build()
+//!     .await
+//!     .unwrap();
+//! let client = server.client();
+//! # }
+//! ```
+mod cassette;
+
+pub use cassette::*;
+
+use crate::encoding::{compress_body, encode_body};
+use crate::http_server::{bytes_response, error_response, query_param};
+use crate::{
+    AccountResponse, Client, HydrusMetadata, MetadataEntry, MetadataResponse, OptionsResponse,
+    Result, Sha256Hash, UpdateFile,
+};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// A local http server speaking the hydrus repository protocol
+/// with crafted responses. The server is stopped when dropped.
+pub struct FakeServer {
+    address: SocketAddr,
+    state: Arc<FakeServerState>,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct FakeServerState {
+    options: Option<Vec<u8>>,
+    account: Option<Vec<u8>>,
+    metadata: Option<HydrusMetadata>,
+    updates: HashMap<Sha256Hash, Vec<u8>>,
+    errors: Mutex<HashMap<String, VecDeque<StatusCode>>>,
+    request_counts: Mutex<HashMap<String, usize>>,
+}
+
+/// Builder for a [FakeServer]
+#[derive(Default)]
+pub struct FakeServerBuilder {
+    options: Option<OptionsResponse>,
+    account: Option<AccountResponse>,
+    metadata: Option<HydrusMetadata>,
+    updates: HashMap<Sha256Hash, Vec<u8>>,
+    errors: HashMap<String, VecDeque<StatusCode>>,
+}
+
+impl FakeServer {
+    /// Creates a new builder for a fake server
+    pub fn builder() -> FakeServerBuilder {
+        FakeServerBuilder::default()
+    }
+
+    /// Returns the address the server is listening on
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns the base url of the server
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Returns a client connected to the server
+    pub fn client(&self) -> Client {
+        Client::new(self.url(), "")
+    }
+
+    /// Returns the number of requests made to the given endpoint path, e.g. `update`
+    pub fn request_count(&self, path: &str) -> usize {
+        self.state
+            .request_counts
+            .lock()
+            .unwrap()
+            .get(path.trim_start_matches('/'))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl FakeServerBuilder {
+    /// Sets the options returned by the `options` endpoint
+    pub fn options(mut self, options: OptionsResponse) -> Self {
+        self.options = Some(options);
+
+        self
+    }
+
+    /// Sets the account returned by the `account` endpoint
+    pub fn account(mut self, account: AccountResponse) -> Self {
+        self.account = Some(account);
+
+        self
+    }
+
+    /// Sets the metadata returned by the `metadata` endpoint.
+    /// Only entries with an update index greater or equal to the
+    /// requested `since` are returned.
+    pub fn metadata(mut self, entries: Vec<MetadataEntry>, next_update_due: u64) -> Self {
+        self.metadata = Some(HydrusMetadata {
+            entries,
+            next_update_due,
+        });
+
+        self
+    }
+
+    /// Adds an update file with the given json content that is compressed
+    /// before being returned by the `update` endpoint
+    pub fn update_json(self, hash: Sha256Hash, update: Value) -> Self {
+        let bytes =
+            compress_body(update.to_string().into_bytes()).expect("failed to compress the update");
+
+        self.raw_update(hash, bytes)
+    }
+
+    /// Adds an update file built with an update builder under its hash
+    pub fn update_file(self, file: UpdateFile) -> Self {
+        self.raw_update(file.hash, file.bytes)
+    }
+
+    /// Adds an update file that is returned as is by the `update` endpoint
+    pub fn raw_update(mut self, hash: Sha256Hash, bytes: Vec<u8>) -> Self {
+        self.updates.insert(hash, bytes);
+
+        self
+    }
+
+    /// Lets the next `times` requests to the given endpoint path fail with the given status
+    pub fn error(mut self, path: &str, status: u16, times: usize) -> Self {
+        let status = StatusCode::from_u16(status).expect("invalid status code");
+        let errors = self
+            .errors
+            .entry(path.trim_start_matches('/').to_string())
+            .or_default();
+        for _ in 0..times {
+            errors.push_back(status);
+        }
+
+        self
+    }
+
+    /// Starts the server on a random local port
+    pub async fn build(self) -> Result<FakeServer> {
+        let options = self.options.as_ref().map(encode_body).transpose()?;
+        let account = self.account.as_ref().map(encode_body).transpose()?;
+        let state = Arc::new(FakeServerState {
+            options,
+            account,
+            metadata: self.metadata,
+            updates: self.updates,
+            errors: Mutex::new(self.errors),
+            request_counts: Mutex::default(),
+        });
+
+        let service_state = Arc::clone(&state);
+        let make_service = make_service_fn(move |_| {
+            let state = Arc::clone(&service_state);
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let state = Arc::clone(&state);
+
+                    async move { Ok::<_, Infallible>(state.handle_request(request)) }
+                }))
+            }
+        });
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))?.serve(make_service);
+        let address = server.local_addr();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = server.await {
+                tracing::error!("fake server failed: {}", e);
+            }
+        });
+
+        Ok(FakeServer {
+            address,
+            state,
+            handle,
+        })
+    }
+}
+
+impl FakeServerState {
+    #[tracing::instrument(skip(self), level = "debug")]
+    fn handle_request(&self, request: Request<Body>) -> Response<Body> {
+        let path = request.uri().path().trim_start_matches('/').to_string();
+        let query = request.uri().query().unwrap_or_default();
+        *self
+            .request_counts
+            .lock()
+            .unwrap()
+            .entry(path.clone())
+            .or_default() += 1;
+
+        if let Some(status) = self
+            .errors
+            .lock()
+            .unwrap()
+            .get_mut(&path)
+            .and_then(|e| e.pop_front())
+        {
+            return error_response(status, format!("fake error {}", status));
+        }
+
+        let body = match path.as_str() {
+            "options" => self.options.clone(),
+            "account" => self.account.clone(),
+            "metadata" => {
+                let since = query_param(query, "since").and_then(|s| s.parse::<u64>().ok());
+                match (&self.metadata, since) {
+                    (Some(metadata), Some(since)) => {
+                        Some(encode_body(&MetadataResponse(metadata.since(since))).unwrap())
+                    }
+                    (_, None) => {
+                        return error_response(StatusCode::BAD_REQUEST, "invalid since parameter")
+                    }
+                    (None, _) => None,
+                }
+            }
+            "update" => match query_param(query, "update_hash").map(Sha256Hash::from_hex) {
+                Some(Ok(hash)) => self.updates.get(&hash).cloned(),
+                _ => {
+                    return error_response(StatusCode::BAD_REQUEST, "invalid update_hash parameter")
+                }
+            },
+            _ => None,
+        };
+
+        match body {
+            Some(bytes) => bytes_response(bytes),
+            None => error_response(StatusCode::NOT_FOUND, "not found"),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::Sha256Hash;
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use rustls::{Certificate, ClientConfig, ServerName};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Accepts server certificates whose SHA-256 fingerprint is pinned.
+/// The chain, hostname and expiry aren't checked as hydrus repositories
+/// use self-signed certificates. The handshake signatures are still verified.
+struct PinnedCertificateVerifier {
+    fingerprints: Vec<Sha256Hash>,
+}
+
+impl ServerCertVerifier for PinnedCertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let fingerprint = Sha256Hash::digest(&end_entity.0);
+
+        if self.fingerprints.contains(&fingerprint) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            tracing::warn!("rejecting certificate with fingerprint {}", fingerprint);
+            Err(rustls::Error::General(format!(
+                "certificate fingerprint {} isn't pinned",
+                fingerprint
+            )))
+        }
+    }
+}
+
+/// Builds a tls config that only accepts certificates with the given fingerprints
+pub(crate) fn pinned_config(fingerprints: Vec<Sha256Hash>) -> ClientConfig {
+    ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(PinnedCertificateVerifier { fingerprints }))
+        .with_no_client_auth()
+}
@@ -0,0 +1,97 @@
+use crate::Result;
+use reqwest::StatusCode;
+use std::future::Future;
+use std::pin::Pin;
+
+/// The future returned by [HttpTransport::send]
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<HttpResponse>> + Send + 'a>>;
+
+/// The method of a [HttpRequest]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HttpMethod {
+    Get,
+    Post,
+}
+
+/// A request made by the [crate::Client] through its [HttpTransport]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    /// The full url including the query
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    /// Returns the value of the header with the given name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The response returned by a [HttpTransport].
+/// Responses that aren't successful are turned into
+/// [crate::Error::ResponseStatus] by the client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Sends the http requests of a [crate::Client].
+///
+/// Rate limits, bandwidth rules and retries are applied by the client
+/// so a transport only has to send a single request.
+pub trait HttpTransport: Send + Sync {
+    /// Sends the request and returns the response
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_>;
+}
+
+/// The default transport sending requests with reqwest
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Creates a new transport using the given reqwest client
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let mut builder = match request.method {
+                HttpMethod::Get => self.client.get(&request.url),
+                HttpMethod::Post => self.client.post(&request.url),
+            };
+            for (name, value) in request.headers {
+                builder = builder.header(name, value);
+            }
+            if let Some(body) = request.body {
+                builder = builder.body(body);
+            }
+            let response = builder.send().await?;
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect();
+
+            Ok(HttpResponse {
+                status: response.status(),
+                headers,
+                body: response.bytes().await?.to_vec(),
+            })
+        })
+    }
+}
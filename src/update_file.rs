@@ -0,0 +1,228 @@
+use crate::constants::{
+    CONTENT_TYPE_MAPPINGS, CONTENT_TYPE_TAG_PARENTS, CONTENT_TYPE_TAG_SIBLINGS,
+    HYDRUS_TYPE_CONTENT_UPDATE, HYDRUS_TYPE_DEFINITIONS_UPDATE,
+};
+use crate::encoding::compress_body;
+use crate::{
+    ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse, Result, Sha256Hash,
+};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A compressed update file as served by a repository
+/// together with the hash it is addressed by
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdateFile {
+    pub hash: Sha256Hash,
+    pub bytes: Vec<u8>,
+}
+
+impl UpdateFile {
+    fn encode(type_id: u64, inner: Vec<Value>) -> Result<Self> {
+        let json = json!([type_id, 1, inner]);
+        let bytes = compress_body(json.to_string().into_bytes())?;
+
+        Ok(Self {
+            hash: Sha256Hash::digest(&bytes),
+            bytes,
+        })
+    }
+}
+
+/// Builds a definitions update file from hash and tag definitions
+#[derive(Clone, Debug, Default)]
+pub struct DefinitionsUpdateBuilder {
+    hashes: BTreeMap<u64, Sha256Hash>,
+    tags: BTreeMap<u64, String>,
+}
+
+impl DefinitionsUpdateBuilder {
+    /// Creates a new builder without any definitions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines the id of a file hash
+    pub fn hash(mut self, id: u64, hash: Sha256Hash) -> Self {
+        self.hashes.insert(id, hash);
+
+        self
+    }
+
+    /// Defines the id of a tag
+    pub fn tag<S: ToString>(mut self, id: u64, tag: S) -> Self {
+        self.tags.insert(id, tag.to_string());
+
+        self
+    }
+
+    /// Returns if no definitions were added
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty() && self.tags.is_empty()
+    }
+
+    /// Encodes the definitions into an update file
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub fn build(self) -> Result<UpdateFile> {
+        let mut definitions = Vec::new();
+
+        if !self.hashes.is_empty() {
+            definitions.push(json!([0, self.hashes.into_iter().collect::<Vec<_>>()]));
+        }
+        if !self.tags.is_empty() {
+            definitions.push(json!([1, self.tags.into_iter().collect::<Vec<_>>()]));
+        }
+
+        UpdateFile::encode(HYDRUS_TYPE_DEFINITIONS_UPDATE, definitions)
+    }
+}
+
+impl From<DefinitionsUpdateResponse> for DefinitionsUpdateBuilder {
+    fn from(update: DefinitionsUpdateResponse) -> Self {
+        Self {
+            hashes: update.hashes.into_iter().collect(),
+            tags: update.tags.into_iter().collect(),
+        }
+    }
+}
+
+/// Builds a content update file from mappings, tag siblings and tag parents
+#[derive(Clone, Debug, Default)]
+pub struct ContentUpdateBuilder {
+    mappings: BTreeMap<(u64, u64), BTreeSet<u64>>,
+    tag_siblings: BTreeSet<(u64, u64, u64)>,
+    tag_parents: BTreeSet<(u64, u64, u64)>,
+}
+
+impl ContentUpdateBuilder {
+    /// Creates a new builder without any content
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or deletes the tag of the files with the given hash ids.
+    /// The change replaces an opposite change of the same mappings.
+    pub fn mappings<I: IntoIterator<Item = u64>>(
+        mut self,
+        action: ContentUpdateAction,
+        tag_id: u64,
+        hash_ids: I,
+    ) -> Self {
+        let hash_ids = hash_ids.into_iter().collect::<BTreeSet<_>>();
+        if let Some(opposite_ids) = self.mappings.get_mut(&(opposite(&action).number(), tag_id)) {
+            opposite_ids.retain(|id| !hash_ids.contains(id));
+        }
+        self.mappings
+            .entry((action.number(), tag_id))
+            .or_default()
+            .extend(hash_ids);
+
+        self
+    }
+
+    /// Adds or deletes a sibling relationship replacing the tag with its sibling.
+    /// The change replaces an opposite change of the same relationship.
+    pub fn tag_sibling(
+        mut self,
+        action: ContentUpdateAction,
+        tag_id: u64,
+        sibling_id: u64,
+    ) -> Self {
+        self.tag_siblings
+            .remove(&(opposite(&action).number(), tag_id, sibling_id));
+        self.tag_siblings
+            .insert((action.number(), tag_id, sibling_id));
+
+        self
+    }
+
+    /// Adds or deletes a parent relationship between two tags.
+    /// The change replaces an opposite change of the same relationship.
+    pub fn tag_parent(
+        mut self,
+        action: ContentUpdateAction,
+        child_id: u64,
+        parent_id: u64,
+    ) -> Self {
+        self.tag_parents
+            .remove(&(opposite(&action).number(), child_id, parent_id));
+        self.tag_parents
+            .insert((action.number(), child_id, parent_id));
+
+        self
+    }
+
+    /// Returns if no content was added
+    pub fn is_empty(&self) -> bool {
+        self.mappings.values().all(BTreeSet::is_empty)
+            && self.tag_siblings.is_empty()
+            && self.tag_parents.is_empty()
+    }
+
+    /// Encodes the content into an update file
+    #[tracing::instrument(skip(self), level = "debug")]
+    pub fn build(self) -> Result<UpdateFile> {
+        let mut content = Vec::new();
+
+        let mut mappings = BTreeMap::<u64, Vec<Value>>::new();
+        for ((action, tag_id), hash_ids) in self.mappings {
+            if !hash_ids.is_empty() {
+                mappings
+                    .entry(action)
+                    .or_default()
+                    .push(json!([tag_id, hash_ids]));
+            }
+        }
+        if !mappings.is_empty() {
+            content.push(json!([
+                CONTENT_TYPE_MAPPINGS,
+                mappings.into_iter().collect::<Vec<_>>()
+            ]));
+        }
+        for (content_type, pairs) in [
+            (CONTENT_TYPE_TAG_SIBLINGS, self.tag_siblings),
+            (CONTENT_TYPE_TAG_PARENTS, self.tag_parents),
+        ] {
+            let mut rows = BTreeMap::<u64, Vec<(u64, u64)>>::new();
+            for (action, a, b) in pairs {
+                rows.entry(action).or_default().push((a, b));
+            }
+            if !rows.is_empty() {
+                content.push(json!([content_type, rows.into_iter().collect::<Vec<_>>()]));
+            }
+        }
+
+        UpdateFile::encode(HYDRUS_TYPE_CONTENT_UPDATE, content)
+    }
+}
+
+impl From<ContentUpdateResponse> for ContentUpdateBuilder {
+    fn from(update: ContentUpdateResponse) -> Self {
+        let mut builder = Self::new();
+
+        for (action, mappings) in update.mappings {
+            for (tag_id, hash_ids) in mappings {
+                builder = builder.mappings(action.clone(), tag_id, hash_ids);
+            }
+        }
+        for (action, siblings) in update.tag_siblings {
+            for (tag_id, sibling_id) in siblings {
+                builder = builder.tag_sibling(action.clone(), tag_id, sibling_id);
+            }
+        }
+        for (action, parents) in update.tag_parents {
+            for (child_id, parent_id) in parents {
+                builder = builder.tag_parent(action.clone(), child_id, parent_id);
+            }
+        }
+
+        builder
+    }
+}
+
+fn opposite(action: &ContentUpdateAction) -> ContentUpdateAction {
+    match action {
+        ContentUpdateAction::Add => ContentUpdateAction::Delete,
+        ContentUpdateAction::Delete => ContentUpdateAction::Add,
+    }
+}
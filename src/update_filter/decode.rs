@@ -0,0 +1,419 @@
+use crate::constants::{
+    CONTENT_TYPE_MAPPINGS, CONTENT_TYPE_TAG_PARENTS, CONTENT_TYPE_TAG_SIBLINGS,
+    HYDRUS_TYPE_CONTENT_UPDATE, HYDRUS_TYPE_DEFINITIONS_UPDATE,
+};
+use crate::hydrus_serializable::content_update::{
+    MappingsUpdateEntry, TagParentsUpdateEntry, TagSiblingsUpdateEntry,
+};
+use crate::hydrus_serializable::definitions_update::{
+    DefinitionsTrait, HashDefinition, TagDefinition,
+};
+use crate::{
+    ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse, UpdateFilter,
+    UpdateResponse,
+};
+use serde::de::{DeserializeSeed, Error, Expected, IgnoredAny, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+/// Deserializes a wrapped update file and discards the rows that aren't kept
+/// by the filter while parsing them. Returns `None` for other serializables.
+pub(crate) struct UpdateSeed<'a>(pub &'a mut UpdateFilter);
+
+impl<'de> DeserializeSeed<'de> for UpdateSeed<'_> {
+    type Value = Option<UpdateResponse>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for UpdateSeed<'_> {
+    type Value = Option<UpdateResponse>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a serialized update file")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let type_id = next_element::<u64, _>(&mut seq, 0, &self)?;
+        let version = next_element::<u64, _>(&mut seq, 1, &self)?;
+        if version != 1 {
+            return Err(A::Error::custom(format!("unsupported version {}", version)));
+        }
+
+        match type_id {
+            HYDRUS_TYPE_DEFINITIONS_UPDATE => {
+                let mut definitions = DefinitionsUpdateResponse {
+                    hashes: HashMap::new(),
+                    tags: HashMap::new(),
+                };
+                let seed = DefinitionsSeed {
+                    filter: self.0,
+                    definitions: &mut definitions,
+                };
+                next_seed(&mut seq, 2, &"definitions", seed)?;
+
+                Ok(Some(UpdateResponse::Definitions(definitions)))
+            }
+            HYDRUS_TYPE_CONTENT_UPDATE => {
+                let mut content = ContentUpdateResponse {
+                    mappings: HashMap::new(),
+                    tag_parents: HashMap::new(),
+                    tag_siblings: HashMap::new(),
+                };
+                let seed = ContentSeed {
+                    filter: self.0,
+                    content: &mut content,
+                };
+                next_seed(&mut seq, 2, &"content", seed)?;
+
+                Ok(Some(UpdateResponse::Content(content)))
+            }
+            _ => {
+                next_element::<IgnoredAny, _>(&mut seq, 2, &"a serializable")?;
+
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Deserializes the `[definition_id, rows]` entries of a definitions update
+struct DefinitionsSeed<'a> {
+    filter: &'a mut UpdateFilter,
+    definitions: &'a mut DefinitionsUpdateResponse,
+}
+
+impl<'de> DeserializeSeed<'de> for DefinitionsSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DefinitionsSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a list of definitions")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let Self {
+            filter,
+            definitions,
+        } = self;
+
+        while seq
+            .next_element_seed(DefinitionEntriesSeed {
+                filter: &mut *filter,
+                definitions: &mut *definitions,
+            })?
+            .is_some()
+        {}
+
+        Ok(())
+    }
+}
+
+/// Deserializes the hash or tag definitions of one `[definition_id, rows]` entry
+struct DefinitionEntriesSeed<'a> {
+    filter: &'a mut UpdateFilter,
+    definitions: &'a mut DefinitionsUpdateResponse,
+}
+
+impl<'de> DeserializeSeed<'de> for DefinitionEntriesSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DefinitionEntriesSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a definition id with its definitions")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let definition_id = next_element::<u64, _>(&mut seq, 0, &self)?;
+        let Self {
+            filter,
+            definitions,
+        } = self;
+
+        if definition_id == HashDefinition::definition_id() {
+            let rows = ForEach::new(|row: HashDefinition| {
+                if filter.keep_hash(row.id, &row.hash) {
+                    definitions.hashes.insert(row.id, row.hash);
+                }
+            });
+            next_seed(&mut seq, 1, &"hash definitions", rows)?;
+        } else if definition_id == TagDefinition::definition_id() {
+            let rows = ForEach::new(|row: TagDefinition| {
+                if filter.keep_tag(row.id, &row.tag) {
+                    definitions.tags.insert(row.id, row.tag);
+                }
+            });
+            next_seed(&mut seq, 1, &"tag definitions", rows)?;
+        } else {
+            next_element::<IgnoredAny, _>(&mut seq, 1, &"definitions")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes the `[content_type, actions]` entries of a content update
+struct ContentSeed<'a> {
+    filter: &'a UpdateFilter,
+    content: &'a mut ContentUpdateResponse,
+}
+
+impl<'de> DeserializeSeed<'de> for ContentSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ContentSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a list of content")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while seq
+            .next_element_seed(ContentEntriesSeed {
+                filter: self.filter,
+                content: &mut *self.content,
+            })?
+            .is_some()
+        {}
+
+        Ok(())
+    }
+}
+
+/// Deserializes the mappings, siblings or parents of one `[content_type, actions]` entry
+struct ContentEntriesSeed<'a> {
+    filter: &'a UpdateFilter,
+    content: &'a mut ContentUpdateResponse,
+}
+
+impl<'de> DeserializeSeed<'de> for ContentEntriesSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ContentEntriesSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a content type with its content")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let content_type = next_element::<u64, _>(&mut seq, 0, &self)?;
+        let Self { filter, content } = self;
+
+        match content_type {
+            CONTENT_TYPE_MAPPINGS => {
+                let actions = ActionsSeed::new(|action, mut row: MappingsUpdateEntry| {
+                    if filter.keep_mapping(row.tag_id, &mut row.hash_ids) {
+                        let mappings = content.mappings.entry(action).or_default();
+                        mappings.insert(row.tag_id, row.hash_ids);
+                    }
+                });
+                next_seed(&mut seq, 1, &"mappings", actions)?;
+            }
+            CONTENT_TYPE_TAG_SIBLINGS => {
+                let actions = ActionsSeed::new(|action, row: TagSiblingsUpdateEntry| {
+                    if filter.keeps_pair(row.tag_id, row.sibling_id) {
+                        let siblings = content.tag_siblings.entry(action).or_default();
+                        siblings.insert(row.tag_id, row.sibling_id);
+                    }
+                });
+                next_seed(&mut seq, 1, &"tag siblings", actions)?;
+            }
+            CONTENT_TYPE_TAG_PARENTS => {
+                let actions = ActionsSeed::new(|action, row: TagParentsUpdateEntry| {
+                    if filter.keeps_pair(row.child_id, row.parent_id) {
+                        let parents = content.tag_parents.entry(action).or_default();
+                        parents.insert(row.child_id, row.parent_id);
+                    }
+                });
+                next_seed(&mut seq, 1, &"tag parents", actions)?;
+            }
+            _ => {
+                next_element::<IgnoredAny, _>(&mut seq, 1, &"content")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes the `[action, rows]` entries of a content type and passes
+/// every row with its action to a function instead of collecting them.
+/// Rows of unknown actions are skipped.
+struct ActionsSeed<T, F> {
+    f: F,
+    row: PhantomData<fn(T)>,
+}
+
+impl<T, F: FnMut(ContentUpdateAction, T)> ActionsSeed<T, F> {
+    fn new(f: F) -> Self {
+        Self {
+            f,
+            row: PhantomData,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(ContentUpdateAction, T)> DeserializeSeed<'de>
+    for ActionsSeed<T, F>
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(ContentUpdateAction, T)> Visitor<'de>
+    for ActionsSeed<T, F>
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a list of actions with their rows")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while seq
+            .next_element_seed(ActionRowsSeed {
+                f: &mut self.f,
+                row: PhantomData,
+            })?
+            .is_some()
+        {}
+
+        Ok(())
+    }
+}
+
+/// Deserializes the rows of one `[action, rows]` entry
+struct ActionRowsSeed<'a, T, F> {
+    f: &'a mut F,
+    row: PhantomData<fn(T)>,
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(ContentUpdateAction, T)> DeserializeSeed<'de>
+    for ActionRowsSeed<'_, T, F>
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(ContentUpdateAction, T)> Visitor<'de>
+    for ActionRowsSeed<'_, T, F>
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("an action with its rows")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let action = next_element::<u64, _>(&mut seq, 0, &self)?;
+
+        match ContentUpdateAction::from_number(action) {
+            Ok(action) => {
+                let f = self.f;
+                let rows = ForEach::new(|row: T| f(action.clone(), row));
+                next_seed(&mut seq, 1, &"rows", rows)?;
+            }
+            Err(_) => {
+                next_element::<IgnoredAny, _>(&mut seq, 1, &"rows")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes a list and passes every element to a function instead of collecting them
+struct ForEach<T, F> {
+    f: F,
+    row: PhantomData<fn(T)>,
+}
+
+impl<T, F: FnMut(T)> ForEach<T, F> {
+    fn new(f: F) -> Self {
+        Self {
+            f,
+            row: PhantomData,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(T)> DeserializeSeed<'de> for ForEach<T, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(T)> Visitor<'de> for ForEach<T, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a list of rows")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while let Some(row) = seq.next_element::<T>()? {
+            (self.f)(row);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the element at the given index of a list that requires it
+fn next_element<'de, T: Deserialize<'de>, A: SeqAccess<'de>>(
+    seq: &mut A,
+    index: usize,
+    expected: &dyn Expected,
+) -> Result<T, A::Error> {
+    seq.next_element()?
+        .ok_or_else(|| A::Error::invalid_length(index, expected))
+}
+
+/// Deserializes the element at the given index of a list that requires it with the seed
+fn next_seed<'de, S: DeserializeSeed<'de>, A: SeqAccess<'de>>(
+    seq: &mut A,
+    index: usize,
+    expected: &dyn Expected,
+    seed: S,
+) -> Result<S::Value, A::Error> {
+    seq.next_element_seed(seed)?
+        .ok_or_else(|| A::Error::invalid_length(index, expected))
+}
@@ -0,0 +1,235 @@
+mod decode;
+
+use crate::{
+    ContentUpdateResponse, DefinitionsUpdateResponse, Error, Result, Sha256Hash, UpdateResponse,
+};
+use decode::UpdateSeed;
+use flate2::read::ZlibDecoder;
+use serde::de::DeserializeSeed;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{Debug, Formatter};
+use std::io::BufReader;
+
+/// A set of file hashes whose mappings are kept by an [UpdateFilter].
+/// Implement it for probabilistic sets like bloom filters to keep
+/// the allowlist small.
+pub trait HashAllowlist {
+    /// Returns if the mappings of the given file hash are kept
+    fn allows(&self, hash: &Sha256Hash) -> bool;
+}
+
+impl HashAllowlist for HashSet<Sha256Hash> {
+    fn allows(&self, hash: &Sha256Hash) -> bool {
+        self.contains(hash)
+    }
+}
+
+impl HashAllowlist for BTreeSet<Sha256Hash> {
+    fn allows(&self, hash: &Sha256Hash) -> bool {
+        self.contains(hash)
+    }
+}
+
+impl<F: Fn(&Sha256Hash) -> bool> HashAllowlist for F {
+    fn allows(&self, hash: &Sha256Hash) -> bool {
+        self(hash)
+    }
+}
+
+/// Discards definitions and content of files and tags that aren't needed.
+///
+/// Hash and tag definitions are filtered first and the ids of the kept
+/// definitions are remembered. Content rows are only kept if all ids they
+/// reference have been kept, so definitions have to be filtered before the
+/// content that uses them, e.g. by streaming with
+/// [crate::StreamOrdering::DefinitionsFirst]. Siblings and parents are kept
+/// if both of their tags have been kept.
+///
+/// [UpdateFilter::decode_update] discards rows while parsing a raw update file,
+/// so the rows that aren't kept are never collected. Only the ids of the filtered
+/// dimensions are remembered, so a namespace filter doesn't collect the ids of all hashes.
+#[derive(Default)]
+pub struct UpdateFilter {
+    hashes: Option<Box<dyn HashAllowlist + Send + Sync>>,
+    namespaces: Option<HashSet<String>>,
+    hash_ids: HashSet<u64>,
+    tag_ids: HashSet<u64>,
+}
+
+impl Debug for UpdateFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UpdateFilter")
+            .field("hashes", &self.hashes.as_ref().map(|_| "HashAllowlist"))
+            .field("namespaces", &self.namespaces)
+            .field("hash_ids", &self.hash_ids.len())
+            .field("tag_ids", &self.tag_ids.len())
+            .finish()
+    }
+}
+
+impl UpdateFilter {
+    /// Creates a new filter that keeps everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keeps the definitions and mappings of allowed file hashes
+    pub fn hashes<A: HashAllowlist + Send + Sync + 'static>(mut self, allowlist: A) -> Self {
+        self.hashes = Some(Box::new(allowlist));
+
+        self
+    }
+
+    /// Only keeps tags of the given namespaces.
+    /// Tags without a namespace belong to the empty namespace.
+    pub fn namespaces<I: IntoIterator<Item = S>, S: ToString>(mut self, namespaces: I) -> Self {
+        self.namespaces = Some(namespaces.into_iter().map(|n| n.to_string()).collect());
+
+        self
+    }
+
+    /// Marks hash and tag ids that were kept by an earlier run of the
+    /// filter so that content referencing them is kept as well
+    pub fn known_ids<H, T>(mut self, hash_ids: H, tag_ids: T) -> Self
+    where
+        H: IntoIterator<Item = u64>,
+        T: IntoIterator<Item = u64>,
+    {
+        self.hash_ids.extend(hash_ids);
+        self.tag_ids.extend(tag_ids);
+
+        self
+    }
+
+    /// Returns the ids of all hash definitions kept so far.
+    /// Empty unless hashes are filtered.
+    pub fn kept_hash_ids(&self) -> &HashSet<u64> {
+        &self.hash_ids
+    }
+
+    /// Returns the ids of all tag definitions kept so far.
+    /// Empty unless namespaces are filtered.
+    pub fn kept_tag_ids(&self) -> &HashSet<u64> {
+        &self.tag_ids
+    }
+
+    /// Returns if the tag is kept by the namespace filter
+    pub fn allows_tag(&self, tag: &str) -> bool {
+        let namespace = tag.split_once(':').map(|(n, _)| n).unwrap_or_default();
+
+        self.namespaces
+            .as_ref()
+            .map(|n| n.contains(namespace))
+            .unwrap_or(true)
+    }
+
+    /// Returns if the file hash is kept by the hash allowlist
+    pub fn allows_hash(&self, hash: &Sha256Hash) -> bool {
+        self.hashes.as_ref().map(|h| h.allows(hash)).unwrap_or(true)
+    }
+
+    /// Decodes a raw zlib compressed update file and discards the definitions and
+    /// content that aren't kept by the filter while parsing it
+    #[tracing::instrument(skip_all, level = "trace")]
+    pub fn decode_update(&mut self, bytes: &[u8]) -> Result<UpdateResponse> {
+        let reader = BufReader::new(ZlibDecoder::new(bytes));
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let update = UpdateSeed(self).deserialize(&mut deserializer)?;
+        deserializer.end()?;
+
+        update.ok_or(Error::Malformed)
+    }
+
+    /// Removes everything from the update that isn't kept by the filter
+    pub fn apply(&mut self, update: &mut UpdateResponse) {
+        match update {
+            UpdateResponse::Definitions(definitions) => self.apply_definitions(definitions),
+            UpdateResponse::Content(content) => self.apply_content(content),
+        }
+    }
+
+    /// Removes hash and tag definitions that aren't kept and
+    /// remembers the ids of the remaining ones
+    #[tracing::instrument(skip_all, level = "trace")]
+    pub fn apply_definitions(&mut self, definitions: &mut DefinitionsUpdateResponse) {
+        if self.hashes.is_some() {
+            definitions
+                .hashes
+                .retain(|id, hash| self.keep_hash(*id, hash));
+        }
+        if self.namespaces.is_some() {
+            definitions.tags.retain(|id, tag| self.keep_tag(*id, tag));
+        }
+    }
+
+    /// Removes content rows that reference hashes or tags that weren't kept
+    #[tracing::instrument(skip_all, level = "trace")]
+    pub fn apply_content(&self, content: &mut ContentUpdateResponse) {
+        let filter_hashes = self.hashes.is_some();
+        let filter_tags = self.namespaces.is_some();
+
+        if filter_hashes || filter_tags {
+            for mappings in content.mappings.values_mut() {
+                mappings.retain(|tag_id, hash_ids| self.keep_mapping(*tag_id, hash_ids));
+            }
+            content.mappings.retain(|_, mappings| !mappings.is_empty());
+        }
+        if filter_tags {
+            for pairs in content
+                .tag_siblings
+                .values_mut()
+                .chain(content.tag_parents.values_mut())
+            {
+                pairs.retain(|a, b| self.keeps_pair(*a, *b));
+            }
+            content.tag_siblings.retain(|_, pairs| !pairs.is_empty());
+            content.tag_parents.retain(|_, pairs| !pairs.is_empty());
+        }
+    }
+
+    /// Returns if the hash definition is kept and remembers its id
+    fn keep_hash(&mut self, id: u64, hash: &Sha256Hash) -> bool {
+        if self.hashes.is_none() {
+            return true;
+        }
+        let keep = self.allows_hash(hash);
+        if keep {
+            self.hash_ids.insert(id);
+        }
+
+        keep
+    }
+
+    /// Returns if the tag definition is kept and remembers its id
+    fn keep_tag(&mut self, id: u64, tag: &str) -> bool {
+        if self.namespaces.is_none() {
+            return true;
+        }
+        let keep = self.allows_tag(tag);
+        if keep {
+            self.tag_ids.insert(id);
+        }
+
+        keep
+    }
+
+    /// Removes the hashes that weren't kept from a mapping and
+    /// returns if the mapping is kept with the remaining hashes
+    fn keep_mapping(&self, tag_id: u64, hash_ids: &mut Vec<u64>) -> bool {
+        if self.namespaces.is_some() && !self.tag_ids.contains(&tag_id) {
+            return false;
+        }
+        if self.hashes.is_some() {
+            hash_ids.retain(|hash_id| self.hash_ids.contains(hash_id));
+            return !hash_ids.is_empty();
+        }
+
+        true
+    }
+
+    /// Returns if a sibling or parent pair is kept
+    fn keeps_pair(&self, tag_id: u64, other_id: u64) -> bool {
+        self.namespaces.is_none()
+            || (self.tag_ids.contains(&tag_id) && self.tag_ids.contains(&other_id))
+    }
+}
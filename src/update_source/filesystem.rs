@@ -0,0 +1,103 @@
+use crate::{
+    Error, HashUpdateStream, MetadataEntry, Result, Sha256Hash, UpdateSource, UpdateStream,
+};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// An update source that reads update files from a local directory.
+/// The files are looked up by their hex encoded hash either directly
+/// in the directory or in the `f<first two hash characters>` subdirectories
+/// as used by the hydrus client file storage.
+#[derive(Clone, Debug)]
+pub struct FileSystemUpdateSource {
+    root: PathBuf,
+}
+
+impl FileSystemUpdateSource {
+    /// Creates a new source reading update files from the given directory
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Returns the directory update files are read from
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the hashes of all update files found in the directory sorted by hash.
+    /// The order doesn't reflect the update indices, use [FileSystemUpdateSource::stream_entries]
+    /// with the stored metadata to apply updates in order.
+    #[tracing::instrument(level = "debug")]
+    pub fn update_hashes(&self) -> Result<Vec<Sha256Hash>> {
+        let mut hashes = Vec::new();
+
+        for entry in fs::read_dir(&self.root)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            if file_type.is_file() {
+                if let Ok(hash) = Sha256Hash::from_hex(name.as_ref()) {
+                    hashes.push(hash);
+                }
+            } else if file_type.is_dir() && name.starts_with('f') {
+                for entry in fs::read_dir(entry.path())? {
+                    let entry = entry?;
+                    if let Ok(hash) = Sha256Hash::from_hex(entry.file_name().to_string_lossy()) {
+                        hashes.push(hash);
+                    }
+                }
+            }
+        }
+
+        hashes.sort_unstable();
+
+        Ok(hashes)
+    }
+
+    /// Returns a stream of the update files with the given hashes in the given order
+    pub fn stream_updates(&self, hashes: Vec<Sha256Hash>) -> HashUpdateStream<Self> {
+        HashUpdateStream::new(self.clone(), hashes)
+    }
+
+    /// Returns a stream of the update files of the given metadata entries
+    /// in the order of their update indices
+    pub fn stream_entries(&self, entries: Vec<MetadataEntry>) -> UpdateStream<Self> {
+        UpdateStream::from_entries(self.clone(), entries)
+    }
+
+    /// Returns the paths an update file with the given hash can be stored at
+    fn update_file_paths(&self, update_hash: &Sha256Hash) -> [PathBuf; 2] {
+        let hex = update_hash.to_hex();
+
+        [
+            self.root.join(&hex),
+            self.root.join(format!("f{}", &hex[..2])).join(&hex),
+        ]
+    }
+}
+
+impl UpdateSource for FileSystemUpdateSource {
+    #[tracing::instrument(skip(self), level = "debug")]
+    async fn get_raw_update(&self, update_hash: &Sha256Hash) -> Result<Vec<u8>> {
+        for path in self.update_file_paths(update_hash) {
+            match tokio::fs::read(&path).await {
+                Ok(bytes) => {
+                    tracing::trace!("read update file {:?}", path);
+                    return Ok(bytes);
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(Error::Io(std::io::Error::new(
+            ErrorKind::NotFound,
+            format!("update file {} not found", update_hash),
+        )))
+    }
+}
@@ -0,0 +1,24 @@
+mod filesystem;
+
+use crate::encoding::decode_body;
+use crate::{Result, Sha256Hash, UpdateResponse};
+use std::future::Future;
+
+pub use filesystem::*;
+
+/// A source that update files can be retrieved from.
+/// Update files are immutable and addressed by their hash, so
+/// every source returns the same file for a given hash.
+pub trait UpdateSource: Clone {
+    /// Returns the raw zlib compressed update file identified by the given hash
+    fn get_raw_update(&self, update_hash: &Sha256Hash) -> impl Future<Output = Result<Vec<u8>>>;
+
+    /// Returns the parsed update file identified by the given hash
+    fn get_update(&self, update_hash: &Sha256Hash) -> impl Future<Output = Result<UpdateResponse>> {
+        async move {
+            let bytes = self.get_raw_update(update_hash).await?;
+
+            decode_body(bytes)
+        }
+    }
+}
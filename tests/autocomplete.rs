@@ -0,0 +1,189 @@
+use hydrus_ptr_client::{
+    AutocompleteIndex, ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse,
+    TagSuggestion, UpdateResponse,
+};
+use std::collections::HashMap;
+
+fn index() -> AutocompleteIndex {
+    let mut index = AutocompleteIndex::new();
+    index.apply(&UpdateResponse::Definitions(DefinitionsUpdateResponse {
+        hashes: HashMap::new(),
+        tags: HashMap::from([
+            (1, String::from("character:samus aran")),
+            (2, String::from("series:metroid")),
+            (3, String::from("samurai")),
+            (4, String::from("creator:sakamoto")),
+            (5, String::from("sand")),
+        ]),
+    }));
+    index.apply(&UpdateResponse::Content(ContentUpdateResponse {
+        mappings: HashMap::from([(
+            ContentUpdateAction::Add,
+            HashMap::from([
+                (1, vec![1, 2, 3]),
+                (2, vec![1]),
+                (3, vec![1, 2]),
+                (4, vec![3]),
+            ]),
+        )]),
+        tag_siblings: HashMap::new(),
+        tag_parents: HashMap::new(),
+    }));
+
+    index
+}
+
+fn suggestion(tag: &str, count: u64) -> TagSuggestion<'_> {
+    TagSuggestion { tag, count }
+}
+
+#[test]
+fn test_prefix_suggestions() {
+    let index = index();
+
+    assert_eq!(index.len(), 5);
+    assert_eq!(
+        index.suggest("Sa", 10),
+        vec![
+            suggestion("character:samus aran", 3),
+            suggestion("samurai", 2),
+            suggestion("creator:sakamoto", 1),
+        ]
+    );
+    assert_eq!(
+        index.suggest("sam", 1),
+        vec![suggestion("character:samus aran", 3)]
+    );
+    assert!(index.suggest("x", 10).is_empty());
+}
+
+#[test]
+fn test_namespace_suggestions() {
+    let index = index();
+
+    assert_eq!(
+        index.suggest("creator:sa", 10),
+        vec![suggestion("creator:sakamoto", 1)]
+    );
+    assert_eq!(
+        index.suggest("series:", 10),
+        vec![suggestion("series:metroid", 1)]
+    );
+    assert_eq!(index.suggest(":sa", 10), vec![suggestion("samurai", 2)]);
+}
+
+#[test]
+fn test_incremental_updates() {
+    let mut index = index();
+    index.apply(&UpdateResponse::Content(ContentUpdateResponse {
+        mappings: HashMap::from([
+            (
+                ContentUpdateAction::Delete,
+                HashMap::from([(1, vec![1, 2, 3])]),
+            ),
+            (ContentUpdateAction::Add, HashMap::from([(5, vec![4])])),
+        ]),
+        tag_siblings: HashMap::new(),
+        tag_parents: HashMap::new(),
+    }));
+
+    assert_eq!(index.count("character:samus aran"), 0);
+    assert_eq!(index.count("sand"), 1);
+    assert_eq!(
+        index.suggest("sa", 10),
+        vec![
+            suggestion("samurai", 2),
+            suggestion("creator:sakamoto", 1),
+            suggestion("sand", 1),
+        ]
+    );
+}
+
+#[test]
+fn test_counts_ignore_existing_and_missing_mappings() {
+    let mut index = index();
+    index.apply(&UpdateResponse::Content(ContentUpdateResponse {
+        mappings: HashMap::from([
+            (ContentUpdateAction::Add, HashMap::from([(2, vec![1, 2])])),
+            (
+                ContentUpdateAction::Delete,
+                HashMap::from([(3, vec![2, 7]), (5, vec![1])]),
+            ),
+        ]),
+        tag_siblings: HashMap::new(),
+        tag_parents: HashMap::new(),
+    }));
+
+    assert_eq!(index.count("series:metroid"), 2);
+    assert_eq!(index.count("samurai"), 1);
+    assert_eq!(index.count("sand"), 0);
+    assert_eq!(
+        index.suggest("sa", 10),
+        vec![
+            suggestion("character:samus aran", 3),
+            suggestion("creator:sakamoto", 1),
+            suggestion("samurai", 1),
+        ]
+    );
+}
+
+#[test]
+fn test_siblings_are_suggested_as_ideal_tag() {
+    let mut index = index();
+    index.apply(&UpdateResponse::Definitions(DefinitionsUpdateResponse {
+        hashes: HashMap::new(),
+        tags: HashMap::from([(6, String::from("metroid series"))]),
+    }));
+    index.apply(&UpdateResponse::Content(ContentUpdateResponse {
+        mappings: HashMap::from([(ContentUpdateAction::Add, HashMap::from([(6, vec![1, 2])]))]),
+        tag_siblings: HashMap::from([(ContentUpdateAction::Add, HashMap::from([(6, 2)]))]),
+        tag_parents: HashMap::new(),
+    }));
+
+    assert_eq!(index.count("metroid series"), 2);
+    assert_eq!(
+        index.suggest("me", 10),
+        vec![suggestion("series:metroid", 2)]
+    );
+    assert_eq!(
+        index.suggest("metroid s", 10),
+        vec![suggestion("series:metroid", 2)]
+    );
+    assert_eq!(
+        index.suggest("series:metroid s", 10),
+        vec![suggestion("series:metroid", 2)]
+    );
+
+    index.apply(&UpdateResponse::Content(ContentUpdateResponse {
+        mappings: HashMap::new(),
+        tag_siblings: HashMap::from([(ContentUpdateAction::Delete, HashMap::from([(6, 2)]))]),
+        tag_parents: HashMap::new(),
+    }));
+
+    assert_eq!(index.count("series:metroid"), 1);
+    assert_eq!(
+        index.suggest("me", 10),
+        vec![
+            suggestion("metroid series", 2),
+            suggestion("series:metroid", 1),
+        ]
+    );
+}
+
+#[test]
+fn test_ranked_and_scanned_prefixes_agree() {
+    let index = index();
+
+    assert_eq!(
+        index.suggest("s", 2),
+        vec![
+            suggestion("character:samus aran", 3),
+            suggestion("samurai", 2),
+        ]
+    );
+    assert_eq!(index.suggest("samu", 10), index.suggest("sa", 2));
+    assert_eq!(
+        index.suggest("character:s", 10),
+        vec![suggestion("character:samus aran", 3)]
+    );
+}
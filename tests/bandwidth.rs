@@ -0,0 +1,119 @@
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{
+    AccountResponse, BandwidthRules, BandwidthTracker, BandwidthType, Client, Error,
+};
+use std::time::{Duration, Instant};
+
+mod common;
+
+#[test]
+fn test_tracker_usage() {
+    let mut tracker = BandwidthTracker::new();
+    tracker.report_request();
+    tracker.report_data(100);
+    tracker.report_request();
+    tracker.report_data(50);
+
+    assert_eq!(tracker.usage(BandwidthType::Requests, None), 2);
+    assert_eq!(tracker.usage(BandwidthType::Data, None), 150);
+    assert_eq!(
+        tracker.usage(BandwidthType::Data, Some(Duration::from_secs(60))),
+        150
+    );
+}
+
+#[test]
+fn test_tracker_wait_time() {
+    let rules = BandwidthRules::new()
+        .add_rule(BandwidthType::Requests, Some(Duration::from_secs(10)), 2)
+        .add_rule(BandwidthType::Data, Some(Duration::from_secs(60)), 1000);
+    let mut tracker = BandwidthTracker::new();
+
+    tracker.report_request();
+    tracker.report_data(400);
+    assert_eq!(tracker.wait_time(&rules).unwrap(), Duration::ZERO);
+
+    tracker.report_request();
+    let wait_time = tracker.wait_time(&rules).unwrap();
+    assert!(wait_time > Duration::from_secs(9) && wait_time <= Duration::from_secs(10));
+
+    tracker.report_data(600);
+    let wait_time = tracker.wait_time(&rules).unwrap();
+    assert!(wait_time > Duration::from_secs(59) && wait_time <= Duration::from_secs(60));
+}
+
+#[test]
+fn test_tracker_total_limit_exceeded() {
+    let rules = BandwidthRules::new().add_rule(BandwidthType::Requests, None, 1);
+    let mut tracker = BandwidthTracker::new();
+    assert_eq!(tracker.wait_time(&rules).unwrap(), Duration::ZERO);
+
+    tracker.report_request();
+    assert!(matches!(
+        tracker.wait_time(&rules),
+        Err(Error::BandwidthExceeded(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_client_delays_requests() {
+    let server = FakeServer::builder()
+        .options(common::options())
+        .build()
+        .await
+        .unwrap();
+    let client = Client::builder()
+        .endpoint(server.url())
+        .bandwidth_rules(BandwidthRules::new().add_rule(
+            BandwidthType::Requests,
+            Some(Duration::from_millis(300)),
+            2,
+        ))
+        .build()
+        .unwrap();
+    let clone = client.clone();
+
+    let start = Instant::now();
+    client.get_options().await.unwrap();
+    clone.get_options().await.unwrap();
+    assert!(start.elapsed() < Duration::from_millis(300));
+
+    client.get_options().await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert_eq!(server.request_count("options"), 3);
+    assert_eq!(client.bandwidth_usage(BandwidthType::Requests, None), 3);
+    assert!(clone.bandwidth_usage(BandwidthType::Data, None) > 0);
+}
+
+#[tokio::test]
+async fn test_account_bandwidth_rules() {
+    let rules = BandwidthRules::new()
+        .add_rule(
+            BandwidthType::Data,
+            Some(Duration::from_secs(86400)),
+            1 << 30,
+        )
+        .add_rule(BandwidthType::Requests, None, 100_000);
+    let server = FakeServer::builder()
+        .account(AccountResponse {
+            account_key: String::from("abc"),
+            account_type: String::from("read only"),
+            created: 1000,
+            expires: None,
+            bandwidth_rules: rules.clone(),
+        })
+        .build()
+        .await
+        .unwrap();
+    let client = server.client();
+    let clone = client.clone();
+
+    let account = client.get_account().await.unwrap();
+    assert_eq!(account.account_key, "abc");
+    assert_eq!(account.account_type, "read only");
+    assert_eq!(account.created, 1000);
+    assert_eq!(account.bandwidth_rules, rules);
+
+    assert_eq!(client.apply_account_bandwidth_rules().await.unwrap(), rules);
+    assert_eq!(clone.bandwidth_rules(), rules);
+}
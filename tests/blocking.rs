@@ -0,0 +1,71 @@
+use hydrus_ptr_client::blocking::Client;
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{MetadataEntry, OptionsResponse, Sha256Hash, UpdateResponse};
+use tokio::runtime::Runtime;
+
+mod common;
+
+fn build_server(runtime: &Runtime) -> FakeServer {
+    let definitions_hash = Sha256Hash::from_hex(common::DEFINITIONS_UPDATE_HASH).unwrap();
+    let content_hash = Sha256Hash::from_hex(common::CONTENT_UPDATE_HASH).unwrap();
+
+    runtime
+        .block_on(
+            FakeServer::builder()
+                .options(OptionsResponse {
+                    server_message: String::from("hello"),
+                    update_period: 100,
+                    nullification_period: 200,
+                    tag_filter: serde_json::json!([]),
+                })
+                .metadata(
+                    vec![MetadataEntry {
+                        update_index: 0,
+                        update_hashes: vec![definitions_hash, content_hash],
+                        time_begin: 0,
+                        time_end: 100,
+                    }],
+                    200,
+                )
+                .update_json(definitions_hash, common::definitions_update_json())
+                .update_json(content_hash, common::content_update_json())
+                .build(),
+        )
+        .unwrap()
+}
+
+#[test]
+fn test_blocking_requests() {
+    let runtime = Runtime::new().unwrap();
+    let server = build_server(&runtime);
+    let client = Client::new(server.url(), "").unwrap();
+
+    assert_eq!(client.get_options().unwrap().server_message, "hello");
+    let metadata = client.get_metadata(0).unwrap();
+    assert_eq!(metadata.0.entries.len(), 1);
+    let update = client
+        .get_update(&metadata.0.entries[0].update_hashes[0])
+        .unwrap();
+    assert!(matches!(update, UpdateResponse::Definitions(_)));
+}
+
+#[test]
+fn test_blocking_update_iterator() {
+    let runtime = Runtime::new().unwrap();
+    let server = build_server(&runtime);
+    let client = hydrus_ptr_client::Client::builder()
+        .endpoint(server.url())
+        .build_blocking()
+        .unwrap();
+
+    let mut updates = client.stream_updates(0).unwrap();
+    let first = updates.next().unwrap().unwrap();
+    assert_eq!(first.update_index, 0);
+    assert!(matches!(first.update, UpdateResponse::Definitions(_)));
+    assert!(matches!(
+        updates.next().unwrap().unwrap().update,
+        UpdateResponse::Content(_)
+    ));
+    assert!(updates.next().is_none());
+    assert_eq!(updates.progress().completed, 2);
+}
@@ -0,0 +1,107 @@
+use hydrus_ptr_client::test_util::{Cassette, FakeServer, RecordingTransport, ReplayTransport};
+use hydrus_ptr_client::{
+    Client, HttpMethod, HttpRequest, HttpResponse, HttpTransport, ReqwestTransport, Sha256Hash,
+    TransportFuture, UpdateResponse,
+};
+use reqwest::StatusCode;
+
+mod common;
+
+#[tokio::test]
+async fn test_recorded_interactions_are_replayed() {
+    let hash = Sha256Hash::from_hex(common::DEFINITIONS_UPDATE_HASH).unwrap();
+    let server = FakeServer::builder()
+        .metadata(Vec::new(), 100)
+        .update_json(hash, common::definitions_update_json())
+        .build()
+        .await
+        .unwrap();
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("cassette.jsonl");
+
+    let recording = Client::builder()
+        .endpoint(server.url())
+        .access_key("secret")
+        .transport(RecordingTransport::new(ReqwestTransport::default(), &path))
+        .build()
+        .unwrap();
+    let recorded_metadata = recording.get_metadata(0).await.unwrap();
+    recording.get_update(&hash).await.unwrap();
+
+    let cassette = Cassette::load(&path).unwrap();
+    assert_eq!(cassette.len(), 2);
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+    assert!(cassette.interactions[0]
+        .request
+        .url
+        .ends_with("/metadata?since=0"));
+    assert!(cassette
+        .interactions
+        .iter()
+        .flat_map(|i| &i.request.headers)
+        .all(|(_, value)| value != "secret"));
+    drop(server);
+
+    let replaying = Client::builder()
+        .endpoint("http://ptr.invalid")
+        .access_key("")
+        .transport(ReplayTransport::open(&path).unwrap())
+        .build()
+        .unwrap();
+    let metadata = replaying.get_metadata(0).await.unwrap();
+    assert_eq!(
+        metadata.0.next_update_due,
+        recorded_metadata.0.next_update_due
+    );
+    let update = replaying.get_update(&hash).await.unwrap();
+    assert!(
+        matches!(update, UpdateResponse::Definitions(definitions) if definitions.tags[&1] == "character:samus aran")
+    );
+    assert!(replaying.get_metadata(1).await.is_err());
+}
+
+/// Answers every request with a session cookie
+struct SessionTransport;
+
+impl HttpTransport for SessionTransport {
+    fn send(&self, _: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async {
+            Ok(HttpResponse {
+                status: StatusCode::OK,
+                headers: vec![(String::from("Set-Cookie"), String::from("session_key=abc"))],
+                body: vec![0, 255],
+            })
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_keys_are_redacted_from_requests_and_responses() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("cassette.jsonl");
+    let recording = RecordingTransport::new(SessionTransport, &path);
+
+    for _ in 0..2 {
+        recording
+            .send(HttpRequest {
+                method: HttpMethod::Get,
+                url: String::from("http://ptr.invalid/account"),
+                headers: vec![
+                    (String::from("Hydrus-Key"), String::from("secret")),
+                    (String::from("Cookie"), String::from("session_key=abc")),
+                ],
+                body: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(content.lines().count(), 2);
+    assert!(!content.contains("secret"));
+    assert!(!content.contains("session_key=abc"));
+
+    let cassette = recording.cassette().unwrap();
+    assert_eq!(cassette.len(), 2);
+    assert_eq!(cassette.interactions[0].response.body, "AP8=");
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "arrow")]
+
+use hydrus_ptr_client::columnar::arrow_array::cast::AsArray;
+use hydrus_ptr_client::columnar::arrow_array::types::UInt64Type;
+use hydrus_ptr_client::columnar::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use hydrus_ptr_client::columnar::{mappings_record_batch, ParquetUpdateWriter};
+use hydrus_ptr_client::{
+    ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse, Sha256Hash,
+    UpdateResponse,
+};
+use std::collections::HashMap;
+use std::fs::File;
+
+mod common;
+
+fn content_update() -> ContentUpdateResponse {
+    ContentUpdateResponse {
+        mappings: HashMap::from([
+            (ContentUpdateAction::Add, HashMap::from([(1, vec![1, 2])])),
+            (ContentUpdateAction::Delete, HashMap::from([(2, vec![3])])),
+        ]),
+        tag_parents: HashMap::new(),
+        tag_siblings: HashMap::from([(ContentUpdateAction::Add, HashMap::from([(1, 2)]))]),
+    }
+}
+
+#[test]
+fn test_mappings_record_batch() {
+    let batch = mappings_record_batch(&content_update(), 7).unwrap();
+    assert_eq!(batch.num_rows(), 3);
+
+    let hash_ids = batch.column(1).as_primitive::<UInt64Type>();
+    let mut hash_ids = hash_ids.values().to_vec();
+    hash_ids.sort();
+    assert_eq!(hash_ids, vec![1, 2, 3]);
+
+    let update_indices = batch.column(3).as_primitive::<UInt64Type>();
+    assert!(update_indices.values().iter().all(|i| *i == 7));
+}
+
+#[test]
+fn test_parquet_writer_partitions() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut writer = ParquetUpdateWriter::new(dir.path());
+    let definitions = DefinitionsUpdateResponse {
+        hashes: HashMap::from([(1, Sha256Hash::from_hex(common::FILE_HASH).unwrap())]),
+        tags: HashMap::from([(1, String::from("character:samus aran"))]),
+    };
+
+    let paths = writer
+        .write_update(3, &UpdateResponse::Definitions(definitions))
+        .unwrap();
+    assert_eq!(paths.len(), 2);
+    let paths = writer
+        .write_update(3, &UpdateResponse::Content(content_update()))
+        .unwrap();
+    assert_eq!(paths.len(), 2);
+    let second_paths = writer
+        .write_update(3, &UpdateResponse::Content(content_update()))
+        .unwrap();
+    assert_ne!(paths, second_paths);
+
+    let mappings_path = dir
+        .path()
+        .join("mappings")
+        .join("update_index=3")
+        .join("part-0.parquet");
+    assert_eq!(paths[0], mappings_path);
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(mappings_path).unwrap())
+        .unwrap()
+        .build()
+        .unwrap();
+    let rows = reader.map(|b| b.unwrap().num_rows()).sum::<usize>();
+    assert_eq!(rows, 3);
+}
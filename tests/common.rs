@@ -1,5 +1,15 @@
-use hydrus_ptr_client::Client;
+#![allow(dead_code)]
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use hydrus_ptr_client::test_util::{RecordingTransport, ReplayTransport};
+use hydrus_ptr_client::{
+    Client, ClientBuilder, OptionsResponse, ReqwestTransport, RetryPolicy, Sha256Hash, UpdateFile,
+};
+use serde_json::{json, Value};
 use std::env;
+use std::io::Write;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex, MutexGuard};
 
 fn setup() {
@@ -7,23 +17,119 @@ fn setup() {
     let mut setup_done: MutexGuard<bool> = SETUP_DONE.lock().unwrap();
 
     if !*setup_done {
-        dotenv::dotenv().expect("failed to initialize dotenv");
+        dotenv::dotenv().ok();
         tracing_subscriber::fmt::init();
         *setup_done = true;
     }
 }
 
-pub fn get_client() -> Client {
+/// Returns a client replaying the cassette with the given name from `tests/cassettes`.
+/// If `PTR_URL` is set and the cassette doesn't exist or `PTR_RECORD` is set, the
+/// requests are sent to the live PTR configured with `PTR_URL` and `PTR_ACCESS_KEY`
+/// and recorded to the cassette. Returns `None` without a cassette or live PTR.
+pub fn cassette_client(name: &str) -> Option<Client> {
     setup();
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("cassettes")
+        .join(format!("{}.jsonl", name));
+
+    if path.exists() && env::var_os("PTR_RECORD").is_none() {
+        let client = Client::builder()
+            .endpoint("https://ptr.invalid")
+            .access_key("")
+            .transport(ReplayTransport::open(path).unwrap())
+            .build()
+            .unwrap();
+        return Some(client);
+    }
+    if env::var_os("PTR_URL").is_none() {
+        eprintln!(
+            "skipping, no cassette {} recorded and PTR_URL isn't set",
+            name
+        );
+        return None;
+    }
+    let reqwest_client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    let client = live_builder()
+        .transport(RecordingTransport::new(
+            ReqwestTransport::new(reqwest_client),
+            path,
+        ))
+        .build()
+        .unwrap();
+
+    Some(client)
+}
+
+fn live_builder() -> ClientBuilder {
     let ptr_url_env = env::var("PTR_URL").ok();
     let ptr_key_env = env::var("PTR_ACCESS_KEY").ok();
 
-    let mut builder = Client::builder().accept_invalid_certs(true);
+    // certificates are accepted by the reqwest client of the recording transport
+    let mut builder = Client::builder().retry_policy(RetryPolicy::new(4));
     if let Some(url) = ptr_url_env {
         builder = builder.endpoint(url);
     }
     if let Some(key) = ptr_key_env {
         builder = builder.access_key(key);
     }
-    builder.build().unwrap()
+    builder
+}
+
+pub const DEFINITIONS_UPDATE_HASH: &str =
+    "4a4d13c1fcdf0cf734927ec4c9637fdac6144512ad7dc919e0f222e7b0e71586";
+pub const CONTENT_UPDATE_HASH: &str =
+    "cd1418ffeba0b8fe46aefa51a7adf1210356523ead658b182762ff61b73ebae5";
+pub const FILE_HASH: &str = "6f8a2e2d4b1f0c3a9e7d5b3c1a0f9e8d7c6b5a4938271605f4e3d2c1b0a99887";
+
+/// Returns the file hash consisting of the given byte
+pub fn file_hash(byte: u8) -> Sha256Hash {
+    Sha256Hash::new([byte; 32])
+}
+
+/// The options served by the test repositories
+pub fn options() -> OptionsResponse {
+    OptionsResponse {
+        server_message: String::from("hello"),
+        update_period: 100,
+        nullification_period: 200,
+        tag_filter: serde_json::json!([]),
+    }
+}
+
+/// Compresses the given json value the way hydrus sends it over the network
+pub fn compress_json(value: &Value) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(value.to_string().as_bytes())
+        .expect("failed to compress json");
+    encoder.finish().expect("failed to compress json")
+}
+
+/// Compresses the given json value into an update file named by its hash
+pub fn update_file(value: &Value) -> UpdateFile {
+    let bytes = compress_json(value);
+
+    UpdateFile {
+        hash: Sha256Hash::digest(&bytes),
+        bytes,
+    }
+}
+
+/// A definitions update that defines the hash id 1 and the tag id 1
+pub fn definitions_update_json() -> Value {
+    json!([
+        36,
+        1,
+        [[0, [[1, FILE_HASH]]], [1, [[1, "character:samus aran"]]]]
+    ])
+}
+
+/// A content update that adds the tag 1 to the hash 1
+pub fn content_update_json() -> Value {
+    json!([34, 1, [[0, [[0, [[1, [1]]]]]]]])
 }
@@ -0,0 +1,162 @@
+use hydrus_ptr_client::{
+    ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse, ExportFormat,
+    ExportRecord, Exporter, Sha256Hash, UpdateResponse,
+};
+use std::collections::HashMap;
+
+mod common;
+
+fn updates() -> Vec<UpdateResponse> {
+    let file_hash = Sha256Hash::from_hex(common::FILE_HASH).unwrap();
+    let definitions = DefinitionsUpdateResponse {
+        hashes: HashMap::from([(1, file_hash)]),
+        tags: HashMap::from([
+            (1, String::from("character:samus aran")),
+            (2, String::from("series:metroid")),
+            (3, String::from("title:\"metroid, prime\"")),
+        ]),
+    };
+    let content = ContentUpdateResponse {
+        mappings: HashMap::from([(
+            ContentUpdateAction::Add,
+            HashMap::from([(1, vec![1, 2]), (3, vec![1])]),
+        )]),
+        tag_parents: HashMap::from([(ContentUpdateAction::Add, HashMap::from([(1, 2)]))]),
+        tag_siblings: HashMap::from([(ContentUpdateAction::Delete, HashMap::from([(3, 4)]))]),
+    };
+
+    vec![
+        UpdateResponse::Content(content.clone()),
+        UpdateResponse::Definitions(definitions),
+        UpdateResponse::Content(content),
+    ]
+}
+
+#[test]
+fn test_export_records() {
+    let mut exporter = Exporter::new(Vec::new(), ExportFormat::JsonLines);
+    let updates = updates();
+
+    assert!(exporter.records(&updates[0]).is_empty());
+    assert_eq!(exporter.unresolved_count(), 5);
+    assert!(exporter.records(&updates[1]).is_empty());
+
+    let mut records = exporter.records(&updates[2]);
+    records.sort_by_key(|r| format!("{:?}", r));
+    assert_eq!(records.len(), 3);
+    assert_eq!(
+        records[0],
+        ExportRecord::Mapping {
+            action: ContentUpdateAction::Add,
+            hash: Sha256Hash::from_hex(common::FILE_HASH).unwrap(),
+            tag: String::from("character:samus aran"),
+        }
+    );
+    assert_eq!(
+        records[2],
+        ExportRecord::TagParent {
+            action: ContentUpdateAction::Add,
+            child: String::from("character:samus aran"),
+            parent: String::from("series:metroid"),
+        }
+    );
+    assert_eq!(exporter.unresolved_count(), 7);
+}
+
+#[test]
+fn test_export_json_lines() {
+    let mut exporter = Exporter::new(Vec::new(), ExportFormat::JsonLines);
+    let count = updates()
+        .iter()
+        .map(|u| exporter.export(u).unwrap())
+        .sum::<usize>();
+    let output = String::from_utf8(exporter.into_inner().unwrap()).unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+
+    assert_eq!(count, 3);
+    assert_eq!(lines.len(), 3);
+    let parent_line = lines.iter().find(|l| l.contains("tag_parent")).unwrap();
+    let value: serde_json::Value = serde_json::from_str(parent_line).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "content_type": "tag_parent",
+            "action": "add",
+            "child": "character:samus aran",
+            "parent": "series:metroid"
+        })
+    );
+}
+
+#[test]
+fn test_export_csv() {
+    let mut exporter = Exporter::new(Vec::new(), ExportFormat::Csv);
+    for update in updates() {
+        exporter.export(&update).unwrap();
+    }
+    let output = String::from_utf8(exporter.into_inner().unwrap()).unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+
+    assert_eq!(
+        lines[0],
+        "content_type,action,hash,tag,sibling,child,parent"
+    );
+    assert_eq!(lines.len(), 4);
+    assert!(lines.contains(
+        &format!(
+            "mapping,add,{},\"title:\"\"metroid, prime\"\"\",,,",
+            common::FILE_HASH
+        )
+        .as_str()
+    ));
+    assert!(lines.contains(&"tag_parent,add,,,,character:samus aran,series:metroid"));
+}
+
+#[test]
+fn test_export_is_sorted_by_ids() {
+    let first_hash = Sha256Hash::from_hex(common::FILE_HASH).unwrap();
+    let second_hash = Sha256Hash::digest(b"second");
+    let definitions = DefinitionsUpdateResponse {
+        hashes: HashMap::from([(1, first_hash), (2, second_hash)]),
+        tags: HashMap::from([
+            (1, String::from("character:samus aran")),
+            (2, String::from("series:metroid")),
+            (3, String::from("title:metroid prime")),
+        ]),
+    };
+    let content = ContentUpdateResponse {
+        mappings: HashMap::from([
+            (ContentUpdateAction::Delete, HashMap::from([(2, vec![1])])),
+            (
+                ContentUpdateAction::Add,
+                HashMap::from([(3, vec![2, 1]), (1, vec![2]), (2, vec![1])]),
+            ),
+        ]),
+        tag_parents: HashMap::new(),
+        tag_siblings: HashMap::new(),
+    };
+    let mut exporter = Exporter::new(Vec::new(), ExportFormat::JsonLines);
+    exporter.records(&UpdateResponse::Definitions(definitions));
+
+    let records = exporter.records(&UpdateResponse::Content(content));
+    let mapping =
+        |action: ContentUpdateAction, hash: Sha256Hash, tag: &str| ExportRecord::Mapping {
+            action,
+            hash,
+            tag: String::from(tag),
+        };
+    assert_eq!(
+        records,
+        vec![
+            mapping(
+                ContentUpdateAction::Add,
+                second_hash,
+                "character:samus aran"
+            ),
+            mapping(ContentUpdateAction::Add, first_hash, "series:metroid"),
+            mapping(ContentUpdateAction::Add, first_hash, "title:metroid prime"),
+            mapping(ContentUpdateAction::Add, second_hash, "title:metroid prime"),
+            mapping(ContentUpdateAction::Delete, first_hash, "series:metroid"),
+        ]
+    );
+}
@@ -0,0 +1,329 @@
+use futures_util::StreamExt;
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{
+    Client, Error, MetadataEntry, OptionsResponse, RetryPolicy, Sha256Hash, StreamOrdering,
+    UpdateResponse,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+mod common;
+
+fn definitions_hash() -> Sha256Hash {
+    Sha256Hash::from_hex(common::DEFINITIONS_UPDATE_HASH).unwrap()
+}
+
+fn content_hash() -> Sha256Hash {
+    Sha256Hash::from_hex(common::CONTENT_UPDATE_HASH).unwrap()
+}
+
+async fn build_server(update_errors: usize) -> FakeServer {
+    FakeServer::builder()
+        .options(OptionsResponse {
+            server_message: String::from("hello"),
+            update_period: 100,
+            nullification_period: 200,
+            tag_filter: serde_json::json!([]),
+        })
+        .metadata(
+            vec![
+                MetadataEntry {
+                    update_index: 0,
+                    update_hashes: vec![definitions_hash()],
+                    time_begin: 0,
+                    time_end: 100,
+                },
+                MetadataEntry {
+                    update_index: 1,
+                    update_hashes: vec![content_hash()],
+                    time_begin: 100,
+                    time_end: 200,
+                },
+            ],
+            300,
+        )
+        .update_json(definitions_hash(), common::definitions_update_json())
+        .update_json(content_hash(), common::content_update_json())
+        .error("update", 500, update_errors)
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_fake_options() {
+    let server = build_server(0).await;
+    let options = server.client().get_options().await.unwrap();
+
+    assert_eq!(options.server_message, "hello");
+    assert_eq!(options.update_period, 100);
+    assert_eq!(options.nullification_period, 200);
+}
+
+#[tokio::test]
+async fn test_fake_metadata() {
+    let server = build_server(0).await;
+    let client = server.client();
+
+    let metadata = client.get_metadata(0).await.unwrap();
+    assert_eq!(metadata.update_hashes().len(), 2);
+    assert_eq!(metadata.0.next_update_due, 300);
+
+    let metadata = client.get_metadata(1).await.unwrap();
+    assert_eq!(metadata.update_hashes(), vec![&content_hash()]);
+}
+
+#[tokio::test]
+async fn test_fake_update() {
+    let server = build_server(0).await;
+    let client = server.client();
+
+    match client.get_update(&content_hash()).await.unwrap() {
+        UpdateResponse::Content(content) => assert_eq!(content.mappings.len(), 1),
+        UpdateResponse::Definitions(_) => panic!("expected a content update"),
+    }
+    let missing = client.get_update(&Sha256Hash::new([0u8; 32])).await;
+    assert!(matches!(missing, Err(Error::ResponseStatus(status, _)) if status == 404));
+}
+
+#[tokio::test]
+async fn test_fake_update_stream_retry() {
+    let server = build_server(1).await;
+    let mut stream = server.client().stream_updates(0).await.unwrap();
+
+    assert!(stream.next().await.unwrap().is_err());
+    assert_eq!(stream.failed_hashes(), &vec![definitions_hash()]);
+    stream.retry_latest();
+
+    let streamed = stream.next().await.unwrap().unwrap();
+    assert!(matches!(streamed.update, UpdateResponse::Definitions(_)));
+    let streamed = stream.next().await.unwrap().unwrap();
+    assert!(matches!(streamed.update, UpdateResponse::Content(_)));
+    assert!(stream.next().await.is_none());
+    assert_eq!(server.request_count("update"), 3);
+}
+
+fn retrying_client(server: &FakeServer, max_attempts: u32) -> Client {
+    Client::builder()
+        .endpoint(server.url())
+        .retry_policy(RetryPolicy::new(max_attempts).base_delay(Duration::from_millis(1)))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_fake_update_automatic_retry() {
+    let server = build_server(2).await;
+    let client = retrying_client(&server, 3);
+
+    let update = client.get_update(&definitions_hash()).await.unwrap();
+    assert!(matches!(update, UpdateResponse::Definitions(_)));
+    assert_eq!(server.request_count("update"), 3);
+}
+
+#[tokio::test]
+async fn test_fake_update_retry_exhausted() {
+    let server = build_server(3).await;
+    let client = retrying_client(&server, 2);
+
+    let result = client.get_update(&definitions_hash()).await;
+    assert!(matches!(result, Err(Error::ResponseStatus(status, _)) if status == 500));
+    assert_eq!(server.request_count("update"), 2);
+}
+
+#[tokio::test]
+async fn test_fake_update_not_found_not_retried() {
+    let server = build_server(0).await;
+    let client = retrying_client(&server, 3);
+
+    let missing = client.get_update(&Sha256Hash::new([0u8; 32])).await;
+    assert!(missing.is_err());
+    assert_eq!(server.request_count("update"), 1);
+}
+
+#[tokio::test]
+async fn test_fake_update_stream_automatic_retry() {
+    let server = build_server(2).await;
+    let stream = retrying_client(&server, 3).stream_updates(0).await.unwrap();
+    let updates = stream.collect::<Vec<_>>().await;
+
+    assert_eq!(updates.len(), 2);
+    assert!(updates.iter().all(|u| u.is_ok()));
+    assert_eq!(server.request_count("update"), 4);
+}
+
+#[test]
+fn test_retry_policy_delay() {
+    let policy = RetryPolicy::new(5)
+        .base_delay(Duration::from_millis(100))
+        .max_delay(Duration::from_millis(300))
+        .jitter(false);
+
+    assert_eq!(policy.delay(1), Duration::from_millis(100));
+    assert_eq!(policy.delay(2), Duration::from_millis(200));
+    assert_eq!(policy.delay(3), Duration::from_millis(300));
+
+    let jittered = RetryPolicy::new(5)
+        .base_delay(Duration::from_millis(100))
+        .delay(2);
+    assert!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn test_fake_update_stream_progress() {
+    let server = build_server(1).await;
+    let reports = Rc::new(RefCell::new(Vec::new()));
+    let callback_reports = Rc::clone(&reports);
+    let mut stream = server
+        .client()
+        .stream_updates(0)
+        .await
+        .unwrap()
+        .on_progress(move |p| callback_reports.borrow_mut().push(p.clone()));
+
+    let progress = stream.progress();
+    assert_eq!(progress.total, 2);
+    assert_eq!(progress.remaining, 2);
+    assert_eq!(progress.update_index, None);
+
+    assert!(stream.next().await.unwrap().is_err());
+    let progress = stream.progress();
+    assert_eq!(progress.failed, 1);
+    assert_eq!(progress.remaining, 1);
+    assert_eq!(progress.update_index, Some(0));
+    stream.retry_latest();
+    assert_eq!(stream.progress().remaining, 2);
+
+    stream.next().await.unwrap().unwrap();
+    stream.next().await.unwrap().unwrap();
+    let progress = stream.progress();
+    assert_eq!(progress.completed, 2);
+    assert_eq!(progress.failed, 0);
+    assert_eq!(progress.remaining, 0);
+    assert!(progress.bytes_downloaded > 0);
+    assert_eq!(progress.update_index, Some(1));
+    assert_eq!(progress.time_begin, Some(100));
+    assert_eq!(progress.time_end, Some(200));
+
+    let reports = reports.borrow();
+    assert_eq!(reports.len(), 3);
+    assert_eq!(reports.last().unwrap(), &progress);
+}
+
+#[tokio::test]
+async fn test_fake_update_stream_entry_context() {
+    let server = build_server(0).await;
+    let stream = server.client().stream_updates(0).await.unwrap();
+    let updates = stream
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].hash, definitions_hash());
+    assert_eq!(updates[0].update_index, 0);
+    assert_eq!(updates[0].time_begin, 0);
+    assert_eq!(updates[0].time_end, 100);
+    assert_eq!(updates[1].hash, content_hash());
+    assert_eq!(updates[1].update_index, 1);
+    assert_eq!(updates[1].time_begin, 100);
+    assert_eq!(updates[1].time_end, 200);
+}
+
+#[tokio::test]
+async fn test_fake_update_stream_definitions_first() {
+    let server = FakeServer::builder()
+        .metadata(
+            vec![MetadataEntry {
+                update_index: 0,
+                update_hashes: vec![content_hash(), definitions_hash()],
+                time_begin: 0,
+                time_end: 100,
+            }],
+            300,
+        )
+        .update_json(definitions_hash(), common::definitions_update_json())
+        .update_json(content_hash(), common::content_update_json())
+        .build()
+        .await
+        .unwrap();
+    let mut stream = server
+        .client()
+        .stream_updates(0)
+        .await
+        .unwrap()
+        .ordering(StreamOrdering::DefinitionsFirst);
+
+    let streamed = stream.next().await.unwrap().unwrap();
+    assert_eq!(streamed.hash, definitions_hash());
+    let streamed = stream.next().await.unwrap().unwrap();
+    assert_eq!(streamed.hash, content_hash());
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_fake_update_stream_holds_back_content() {
+    let server = FakeServer::builder()
+        .metadata(
+            vec![
+                MetadataEntry {
+                    update_index: 0,
+                    update_hashes: vec![definitions_hash()],
+                    time_begin: 0,
+                    time_end: 100,
+                },
+                MetadataEntry {
+                    update_index: 1,
+                    update_hashes: vec![content_hash()],
+                    time_begin: 100,
+                    time_end: 200,
+                },
+            ],
+            300,
+        )
+        .update_json(definitions_hash(), common::definitions_update_json())
+        .update_json(content_hash(), common::content_update_json())
+        .error("update", 500, 1)
+        .build()
+        .await
+        .unwrap();
+    let mut stream = server
+        .client()
+        .stream_updates(0)
+        .await
+        .unwrap()
+        .ordering(StreamOrdering::DefinitionsFirst);
+
+    assert!(stream.next().await.unwrap().is_err());
+    let held_back = stream.next().await.unwrap();
+    assert!(matches!(held_back, Err(Error::HeldBack(hashes)) if hashes == vec![content_hash()]));
+    assert!(stream.next().await.is_none());
+    assert_eq!(stream.progress().held_back, 1);
+
+    stream.retry_latest();
+    let streamed = stream.next().await.unwrap().unwrap();
+    assert!(matches!(streamed.update, UpdateResponse::Definitions(_)));
+    let streamed = stream.next().await.unwrap().unwrap();
+    assert!(matches!(streamed.update, UpdateResponse::Content(_)));
+    assert_eq!(streamed.update_index, 1);
+    assert!(stream.next().await.is_none());
+    assert_eq!(stream.progress().held_back, 0);
+}
+
+#[tokio::test]
+async fn test_fake_update_stream_skips_earlier_content() {
+    let server = build_server(0).await;
+    let stream = server
+        .client()
+        .stream_updates(0)
+        .await
+        .unwrap()
+        .skip_content_before(2);
+
+    let updates = stream.map(|u| u.unwrap().hash).collect::<Vec<_>>().await;
+    assert_eq!(updates, vec![definitions_hash()]);
+    assert_eq!(server.request_count("update"), 2);
+}
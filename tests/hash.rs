@@ -0,0 +1,35 @@
+use hydrus_ptr_client::Sha256Hash;
+
+const HASH: &str = "4a4d13c1fcdf0cf734927ec4c9637fdac6144512ad7dc919e0f222e7b0e71586";
+
+#[test]
+fn test_hash_roundtrip() {
+    let hash: Sha256Hash = HASH.parse().unwrap();
+    assert_eq!(hash.as_bytes()[0], 0x4a);
+    assert_eq!(hash.to_string(), HASH);
+}
+
+#[test]
+fn test_hash_uppercase() {
+    let hash = Sha256Hash::from_hex(HASH.to_uppercase()).unwrap();
+    assert_eq!(hash.to_hex(), HASH);
+}
+
+#[test]
+fn test_invalid_hash() {
+    assert!(Sha256Hash::from_hex("").is_err());
+    assert!(Sha256Hash::from_hex(&HASH[1..]).is_err());
+    assert!(Sha256Hash::from_hex(format!("{}00", HASH)).is_err());
+    assert!(Sha256Hash::from_hex(HASH.replace('4', "g")).is_err());
+}
+
+#[test]
+fn test_hash_serde() {
+    let hash: Sha256Hash = serde_json::from_str(&format!("\"{}\"", HASH)).unwrap();
+    assert_eq!(hash.to_hex(), HASH);
+    assert_eq!(
+        serde_json::to_string(&hash).unwrap(),
+        format!("\"{}\"", HASH)
+    );
+    assert!(serde_json::from_str::<Sha256Hash>("\"abc\"").is_err());
+}
@@ -0,0 +1,79 @@
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{HydrusMetadata, MetadataEntry, Sha256Hash};
+
+fn entry(update_index: u64, hash: u8) -> MetadataEntry {
+    MetadataEntry {
+        update_index,
+        update_hashes: vec![Sha256Hash::new([hash; 32])],
+        time_begin: update_index * 100,
+        time_end: (update_index + 1) * 100,
+    }
+}
+
+fn snapshot(entries: Vec<MetadataEntry>) -> HydrusMetadata {
+    HydrusMetadata {
+        entries,
+        next_update_due: 1000,
+    }
+}
+
+#[test]
+fn test_entry_lookup() {
+    let metadata = snapshot(vec![entry(0, 0), entry(1, 1), entry(2, 2)]);
+
+    assert_eq!(metadata.entry(1), Some(&entry(1, 1)));
+    assert_eq!(metadata.entry(3), None);
+    assert_eq!(metadata.entry_at(150), Some(&entry(1, 1)));
+    assert_eq!(metadata.entry_at(300), None);
+    assert_eq!(metadata.latest_update_index(), Some(2));
+    assert_eq!(snapshot(Vec::new()).latest_update_index(), None);
+}
+
+#[test]
+fn test_ranges() {
+    let metadata = snapshot(vec![entry(0, 0), entry(1, 1), entry(2, 2), entry(3, 3)]);
+
+    let indices = |m: HydrusMetadata| m.entries.iter().map(|e| e.update_index).collect::<Vec<_>>();
+    assert_eq!(indices(metadata.index_range(1..3)), vec![1, 2]);
+    assert_eq!(indices(metadata.index_range(..=1)), vec![0, 1]);
+    assert_eq!(indices(metadata.since(2)), vec![2, 3]);
+    assert_eq!(indices(metadata.time_range(150, 250)), vec![1, 2]);
+    assert_eq!(indices(metadata.time_range(100, 200)), vec![1]);
+    assert_eq!(metadata.index_range(1..3).next_update_due, 1000);
+}
+
+#[test]
+fn test_diff() {
+    let previous = snapshot(vec![entry(0, 0), entry(1, 1), entry(2, 2)]);
+    let mut changed = entry(2, 2);
+    changed.update_hashes.push(Sha256Hash::new([9; 32]));
+    let current = snapshot(vec![entry(1, 1), changed.clone(), entry(3, 3)]);
+
+    let diff = current.diff(&previous);
+    assert_eq!(diff.added, vec![entry(3, 3)]);
+    assert_eq!(diff.changed, vec![changed]);
+    assert_eq!(diff.removed, vec![0]);
+    assert!(!diff.is_empty());
+    assert!(current.diff(&current).is_empty());
+}
+
+#[tokio::test]
+async fn test_metadata_range() {
+    let server = FakeServer::builder()
+        .metadata(
+            vec![entry(0, 0), entry(1, 1), entry(2, 2), entry(3, 3)],
+            1000,
+        )
+        .build()
+        .await
+        .unwrap();
+    let client = server.client();
+
+    let metadata = client.get_metadata_range(1..3).await.unwrap();
+    assert_eq!(metadata.0.entries, vec![entry(1, 1), entry(2, 2)]);
+    assert_eq!(metadata.latest_update_index(), Some(2));
+    assert_eq!(metadata.entry(2), Some(&entry(2, 2)));
+
+    let all = client.get_metadata(0).await.unwrap();
+    assert_eq!(all.diff(&metadata).added, vec![entry(0, 0), entry(3, 3)]);
+}
@@ -0,0 +1,208 @@
+#![cfg(feature = "mirror")]
+
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{
+    AccountResponse, BandwidthRules, Client, MetadataEntry, Mirror, OptionsResponse, PtrStorage,
+    ServerTls, UpdateResponse,
+};
+use reqwest::header::{COOKIE, SET_COOKIE};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+fn free_address() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+async fn synced_mirror() -> (Arc<Mirror>, MetadataEntry, tempfile::TempDir) {
+    let definitions = common::update_file(&common::definitions_update_json());
+    let content = common::update_file(&common::content_update_json());
+    let entry = MetadataEntry {
+        update_index: 0,
+        update_hashes: vec![definitions.hash, content.hash],
+        time_begin: 0,
+        time_end: 100,
+    };
+    let upstream = FakeServer::builder()
+        .options(OptionsResponse {
+            server_message: String::from("hello"),
+            update_period: 100,
+            nullification_period: 200,
+            tag_filter: serde_json::json!([]),
+        })
+        .account(AccountResponse {
+            account_key: String::from("abc"),
+            account_type: String::from("read only"),
+            created: 1000,
+            expires: None,
+            bandwidth_rules: BandwidthRules::new(),
+        })
+        .metadata(vec![entry.clone()], 200)
+        .update_file(definitions)
+        .update_file(content)
+        .build()
+        .await
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let storage = PtrStorage::open(dir.path()).unwrap();
+    let mirror = Arc::new(Mirror::new(upstream.client(), storage).unwrap());
+    mirror.sync().await.unwrap();
+
+    (mirror, entry, dir)
+}
+
+#[tokio::test]
+async fn test_mirror_sync_and_serve() {
+    let definitions = common::update_file(&common::definitions_update_json());
+    let content = common::update_file(&common::content_update_json());
+    let (definitions_hash, content_hash) = (definitions.hash, content.hash);
+    let upstream = FakeServer::builder()
+        .options(OptionsResponse {
+            server_message: String::from("hello"),
+            update_period: 100,
+            nullification_period: 200,
+            tag_filter: serde_json::json!([]),
+        })
+        .metadata(
+            vec![MetadataEntry {
+                update_index: 0,
+                update_hashes: vec![definitions_hash, content_hash],
+                time_begin: 0,
+                time_end: 100,
+            }],
+            200,
+        )
+        .update_file(definitions)
+        .update_file(content)
+        .error("update", 500, 1)
+        .build()
+        .await
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let storage = PtrStorage::open(dir.path()).unwrap();
+    let mirror = Arc::new(Mirror::new(upstream.client(), storage).unwrap());
+    assert!(mirror.sync().await.is_err());
+    assert!(mirror.storage().read_metadata().unwrap().is_none());
+
+    let summary = mirror.sync().await.unwrap();
+    assert_eq!(summary.total_updates, 2);
+    assert_eq!(summary.downloaded_updates, 2);
+    assert_eq!(mirror.sync().await.unwrap().downloaded_updates, 0);
+    drop(upstream);
+
+    let address = free_address();
+    tokio::spawn(Arc::clone(&mirror).serve(address));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = Client::new(format!("http://{}", address), "");
+    let options = client.get_options().await.unwrap();
+    assert_eq!(options.update_period, 100);
+
+    let metadata = client.get_metadata(0).await.unwrap();
+    assert_eq!(metadata.update_hashes().len(), 2);
+    let metadata = client.get_metadata(1).await.unwrap();
+    assert!(metadata.update_hashes().is_empty());
+
+    let update = client.get_update(&definitions_hash).await.unwrap();
+    assert!(matches!(update, UpdateResponse::Definitions(_)));
+    let update = client.get_update(&content_hash).await.unwrap();
+    assert!(matches!(update, UpdateResponse::Content(_)));
+}
+
+#[tokio::test]
+async fn test_mirror_serves_account() {
+    let (mirror, _, _dir) = synced_mirror().await;
+    let address = free_address();
+    tokio::spawn(Arc::clone(&mirror).serve(address));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = Client::new(format!("http://{}", address), "");
+    let account = client.get_account().await.unwrap();
+    assert_eq!(account.account_key, "abc");
+    assert_eq!(account.account_type, "read only");
+}
+
+#[tokio::test]
+async fn test_mirror_session_key_handshake() {
+    let (mirror, _, _dir) = synced_mirror().await;
+    let address = free_address();
+    tokio::spawn(Arc::clone(&mirror).serve(address));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let http = reqwest::Client::new();
+
+    let response = http
+        .get(format!("http://{}/metadata?since=0", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 419);
+
+    let response = http
+        .get(format!("http://{}/session_key", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = http
+        .get(format!("http://{}/session_key", address))
+        .header("Hydrus-Key", "abc")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+    assert!(cookie.starts_with("session_key="));
+    let session_cookie = cookie.split(';').next().unwrap().to_string();
+
+    let response = http
+        .get(format!("http://{}/metadata?since=0", address))
+        .header(COOKIE, session_cookie)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let response = http
+        .get(format!("http://{}/metadata?since=0", address))
+        .header(COOKIE, "session_key=unknown")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 419);
+}
+
+#[tokio::test]
+async fn test_mirror_serves_https() {
+    let (mirror, entry, _dir) = synced_mirror().await;
+    let tls = ServerTls::from_pem(
+        include_bytes!("certs/server.crt"),
+        include_bytes!("certs/server.key"),
+    )
+    .unwrap();
+    let address = free_address();
+    tokio::spawn(Arc::clone(&mirror).serve_tls(address, tls));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = Client::builder()
+        .endpoint(format!("https://localhost:{}", address.port()))
+        .access_key("")
+        .accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    let metadata = client.get_metadata(0).await.unwrap();
+    assert_eq!(metadata.0.entries, vec![entry]);
+}
+
+#[test]
+fn test_server_tls_requires_a_key() {
+    let result = ServerTls::from_pem(include_bytes!("certs/server.crt"), b"");
+    assert!(result.is_err());
+}
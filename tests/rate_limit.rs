@@ -0,0 +1,83 @@
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{Client, OptionsResponse, Sha256Hash};
+use std::time::{Duration, Instant};
+
+mod common;
+
+async fn build_server() -> FakeServer {
+    FakeServer::builder()
+        .options(OptionsResponse {
+            server_message: String::from("hello"),
+            update_period: 100,
+            nullification_period: 200,
+            tag_filter: serde_json::json!([]),
+        })
+        .update_json(
+            Sha256Hash::from_hex(common::DEFINITIONS_UPDATE_HASH).unwrap(),
+            common::definitions_update_json(),
+        )
+        .update_json(
+            Sha256Hash::from_hex(common::CONTENT_UPDATE_HASH).unwrap(),
+            common::content_update_json(),
+        )
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_requests_per_second_shared_by_clones() {
+    let server = build_server().await;
+    let client = Client::builder()
+        .endpoint(server.url())
+        .max_requests_per_second(10.0)
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let tasks = (0..4)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.get_options().await.unwrap() })
+        })
+        .collect::<Vec<_>>();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert_eq!(server.request_count("options"), 4);
+}
+
+#[tokio::test]
+async fn test_update_download_delay() {
+    let server = build_server().await;
+    let client = Client::builder()
+        .endpoint(server.url())
+        .update_download_delay(Duration::from_millis(200))
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    client.get_options().await.unwrap();
+    client.get_options().await.unwrap();
+    assert!(start.elapsed() < Duration::from_millis(200));
+
+    client
+        .get_update(&Sha256Hash::from_hex(common::DEFINITIONS_UPDATE_HASH).unwrap())
+        .await
+        .unwrap();
+    client
+        .get_update(&Sha256Hash::from_hex(common::CONTENT_UPDATE_HASH).unwrap())
+        .await
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[test]
+fn test_invalid_request_rate() {
+    assert!(Client::builder()
+        .max_requests_per_second(0.0)
+        .build()
+        .is_err());
+}
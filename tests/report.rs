@@ -0,0 +1,209 @@
+use hydrus_ptr_client::{
+    ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse, IndexChurn,
+    NamespaceActivity, RelationshipKind, ReportCollector, Sha256Hash, StreamedUpdate,
+    UpdateResponse,
+};
+use std::collections::HashMap;
+
+fn streamed(update_index: u64, update: UpdateResponse) -> StreamedUpdate {
+    StreamedUpdate {
+        update_index,
+        hash: Sha256Hash::new([update_index as u8; 32]),
+        time_begin: update_index * 100,
+        time_end: (update_index + 1) * 100,
+        update,
+    }
+}
+
+fn collector() -> ReportCollector {
+    let mut collector = ReportCollector::new();
+    collector.apply(&streamed(
+        0,
+        UpdateResponse::Definitions(DefinitionsUpdateResponse {
+            hashes: HashMap::new(),
+            tags: HashMap::from([
+                (1, String::from("character:samus aran")),
+                (2, String::from("series:metroid")),
+                (3, String::from("samus")),
+            ]),
+        }),
+    ));
+    collector.apply(&streamed(
+        0,
+        UpdateResponse::Content(ContentUpdateResponse {
+            mappings: HashMap::from([(
+                ContentUpdateAction::Add,
+                HashMap::from([(1, vec![1, 2, 3]), (2, vec![1]), (3, vec![4])]),
+            )]),
+            tag_siblings: HashMap::from([(ContentUpdateAction::Add, HashMap::from([(3, 1)]))]),
+            tag_parents: HashMap::new(),
+        }),
+    ));
+    collector.apply(&streamed(
+        1,
+        UpdateResponse::Content(ContentUpdateResponse {
+            mappings: HashMap::from([(
+                ContentUpdateAction::Delete,
+                HashMap::from([(3, vec![4]), (4, vec![5])]),
+            )]),
+            tag_siblings: HashMap::new(),
+            tag_parents: HashMap::from([(ContentUpdateAction::Add, HashMap::from([(1, 2)]))]),
+        }),
+    ));
+
+    collector
+}
+
+#[test]
+fn test_tag_and_namespace_activity() {
+    let report = collector().report(2);
+
+    assert_eq!(report.time_begin, Some(0));
+    assert_eq!(report.time_end, Some(200));
+    assert_eq!(report.tags.len(), 2);
+    assert_eq!(report.tags[0].tag.as_deref(), Some("character:samus aran"));
+    assert_eq!(report.tags[0].added, 3);
+    assert_eq!(report.tags[1].tag.as_deref(), Some("samus"));
+    assert_eq!((report.tags[1].added, report.tags[1].deleted), (1, 1));
+
+    let full_report = collector().report(10);
+    let unknown = full_report.tags.iter().find(|t| t.tag_id == 4).unwrap();
+    assert_eq!(unknown.tag, None);
+    assert_eq!(
+        full_report.namespaces,
+        vec![
+            NamespaceActivity {
+                namespace: Some(String::from("character")),
+                added: 3,
+                deleted: 0,
+            },
+            NamespaceActivity {
+                namespace: Some(String::new()),
+                added: 1,
+                deleted: 1,
+            },
+            NamespaceActivity {
+                namespace: None,
+                added: 0,
+                deleted: 1,
+            },
+            NamespaceActivity {
+                namespace: Some(String::from("series")),
+                added: 1,
+                deleted: 0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_equal_activity_is_ordered_by_name() {
+    let mut collector = ReportCollector::new();
+    collector.apply(&streamed(
+        0,
+        UpdateResponse::Definitions(DefinitionsUpdateResponse {
+            hashes: HashMap::new(),
+            tags: HashMap::from([
+                (1, String::from("series:metroid")),
+                (2, String::from("creator:sakamoto")),
+                (3, String::from("samus")),
+            ]),
+        }),
+    ));
+    collector.apply(&streamed(
+        0,
+        UpdateResponse::Content(ContentUpdateResponse {
+            mappings: HashMap::from([(
+                ContentUpdateAction::Add,
+                HashMap::from([(1, vec![1]), (2, vec![1]), (3, vec![1])]),
+            )]),
+            tag_siblings: HashMap::new(),
+            tag_parents: HashMap::new(),
+        }),
+    ));
+    let report = collector.report(10);
+
+    assert_eq!(
+        report
+            .tags
+            .iter()
+            .map(|t| t.tag.as_deref().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["creator:sakamoto", "samus", "series:metroid"]
+    );
+    assert_eq!(
+        report
+            .namespaces
+            .iter()
+            .map(|n| n.namespace.as_deref().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["", "creator", "series"]
+    );
+}
+
+#[test]
+fn test_churn_and_relationship_changes() {
+    let report = collector().report(10);
+
+    assert_eq!(
+        report.churn,
+        vec![
+            IndexChurn {
+                update_index: 0,
+                time_begin: 0,
+                time_end: 100,
+                mappings_added: 5,
+                siblings_added: 1,
+                ..Default::default()
+            },
+            IndexChurn {
+                update_index: 1,
+                time_begin: 100,
+                time_end: 200,
+                mappings_deleted: 2,
+                parents_added: 1,
+                ..Default::default()
+            },
+        ]
+    );
+    assert_eq!(report.relationship_changes.len(), 2);
+    let sibling = &report.relationship_changes[0];
+    assert_eq!(sibling.kind, RelationshipKind::Sibling);
+    assert_eq!(sibling.action, ContentUpdateAction::Add);
+    assert_eq!(sibling.tag.as_deref(), Some("samus"));
+    assert_eq!(sibling.other.as_deref(), Some("character:samus aran"));
+    let parent = &report.relationship_changes[1];
+    assert_eq!(parent.kind, RelationshipKind::Parent);
+    assert_eq!(parent.update_index, 1);
+    assert_eq!(parent.other.as_deref(), Some("series:metroid"));
+}
+
+#[test]
+fn test_unknown_tags_are_ordered_by_id() {
+    let mut collector = ReportCollector::new();
+    collector.apply(&streamed(
+        0,
+        UpdateResponse::Content(ContentUpdateResponse {
+            mappings: HashMap::from([(
+                ContentUpdateAction::Add,
+                HashMap::from([(3, vec![1]), (1, vec![1]), (2, vec![1])]),
+            )]),
+            tag_siblings: HashMap::new(),
+            tag_parents: HashMap::new(),
+        }),
+    ));
+    let report = collector.report(2);
+
+    assert_eq!(
+        report.tags.iter().map(|t| t.tag_id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(
+        report.namespaces,
+        vec![NamespaceActivity {
+            namespace: None,
+            added: 3,
+            deleted: 0,
+        }]
+    );
+}
@@ -0,0 +1,275 @@
+#![cfg(feature = "server")]
+
+use hydrus_ptr_client::{
+    BandwidthRules, Client, ClientToServerUpdate, ContentUpdateAction, Error, PtrStorage,
+    RepositoryServer, ServerTls, Sha256Hash, UpdateResponse, UpdateSource,
+};
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+const ACCESS_KEY: &str = "0123456789abcdef";
+
+async fn open_server(root: &Path) -> Arc<RepositoryServer> {
+    let storage = PtrStorage::open(root).unwrap();
+    let server = RepositoryServer::open(storage, common::options())
+        .await
+        .unwrap()
+        .access_key(ACCESS_KEY);
+
+    Arc::new(server)
+}
+
+fn free_address() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+async fn serve(server: &Arc<RepositoryServer>) -> String {
+    let address = free_address();
+    tokio::spawn(Arc::clone(server).serve(address));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    format!("http://{}", address)
+}
+
+fn client(url: &str, access_key: &str) -> Client {
+    Client::builder()
+        .endpoint(url)
+        .access_key(access_key)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_posted_content_is_published() {
+    let root = tempfile::tempdir().unwrap();
+    let server = open_server(root.path()).await;
+    let url = serve(&server).await;
+    let client = client(&url, ACCESS_KEY);
+
+    let options = client.get_options().await.unwrap();
+    assert_eq!(options.server_message, "hello");
+
+    client
+        .post_update(
+            &ClientToServerUpdate::new()
+                .add_mappings(
+                    "character:samus aran",
+                    vec![common::file_hash(1), common::file_hash(2)],
+                )
+                .add_tag_sibling("samus", "character:samus aran")
+                .add_tag_parent("character:samus aran", "series:metroid"),
+        )
+        .await
+        .unwrap();
+    let entry = server.publish_updates().unwrap().unwrap();
+    assert_eq!(entry.update_index, 0);
+    assert_eq!(entry.update_hashes.len(), 2);
+
+    let metadata = client.get_metadata(0).await.unwrap();
+    assert_eq!(metadata.0.entries, vec![entry.clone()]);
+
+    let definitions = match client.get_update(&entry.update_hashes[0]).await.unwrap() {
+        UpdateResponse::Definitions(definitions) => definitions,
+        other => panic!("expected definitions update, got {:?}", other),
+    };
+    assert_eq!(definitions.hashes.len(), 2);
+    assert_eq!(definitions.tags.len(), 3);
+    let tag_id = |tag: &str| {
+        *definitions
+            .tags
+            .iter()
+            .find(|(_, t)| t.as_str() == tag)
+            .unwrap()
+            .0
+    };
+    let samus_id = tag_id("character:samus aran");
+
+    let content = match client.get_update(&entry.update_hashes[1]).await.unwrap() {
+        UpdateResponse::Content(content) => content,
+        other => panic!("expected content update, got {:?}", other),
+    };
+    let mut hash_ids = content.mappings[&ContentUpdateAction::Add][&samus_id].clone();
+    hash_ids.sort_unstable();
+    assert_eq!(hash_ids, vec![1, 2]);
+    assert_eq!(
+        content.tag_siblings[&ContentUpdateAction::Add][&tag_id("samus")],
+        samus_id
+    );
+    assert_eq!(
+        content.tag_parents[&ContentUpdateAction::Add][&samus_id],
+        tag_id("series:metroid")
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_access_key_is_rejected() {
+    let root = tempfile::tempdir().unwrap();
+    let server = open_server(root.path()).await;
+    let url = serve(&server).await;
+    let client = client(&url, "invalid");
+
+    assert!(client.get_options().await.is_ok());
+    let result = client.get_metadata(0).await;
+    assert!(matches!(result, Err(Error::ResponseStatus(status, _)) if status.as_u16() == 403));
+}
+
+#[tokio::test]
+async fn test_ids_are_restored_after_restart() {
+    let root = tempfile::tempdir().unwrap();
+    {
+        let server = open_server(root.path()).await;
+        server.process_update(
+            &ClientToServerUpdate::new().add_mappings("tag", vec![common::file_hash(1)]),
+        );
+        server.publish_updates().unwrap().unwrap();
+    }
+    let server = open_server(root.path()).await;
+    server.process_update(
+        &ClientToServerUpdate::new()
+            .add_mappings("tag", vec![common::file_hash(1), common::file_hash(2)]),
+    );
+    let entry = server.publish_updates().unwrap().unwrap();
+    assert_eq!(entry.update_index, 1);
+
+    let definitions = match server
+        .storage()
+        .update_source()
+        .get_update(&entry.update_hashes[0])
+        .await
+        .unwrap()
+    {
+        UpdateResponse::Definitions(definitions) => definitions,
+        other => panic!("expected definitions update, got {:?}", other),
+    };
+    assert!(definitions.tags.is_empty());
+    assert_eq!(definitions.hashes.len(), 1);
+    assert_eq!(definitions.hashes[&2], common::file_hash(2));
+}
+
+#[tokio::test]
+async fn test_empty_periods_are_skipped() {
+    let root = tempfile::tempdir().unwrap();
+    let server = open_server(root.path()).await;
+    let next_update_due = server.metadata().next_update_due;
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    assert_eq!(server.publish_updates().unwrap(), None);
+    let metadata = server.metadata();
+    assert!(metadata.entries.is_empty());
+    assert!(metadata.next_update_due > next_update_due);
+
+    server.process_update(
+        &ClientToServerUpdate::new().add_mappings("tag", vec![common::file_hash(1)]),
+    );
+    let entry = server.publish_updates().unwrap().unwrap();
+    assert_eq!(entry.update_index, 0);
+    assert_eq!(server.publish_updates().unwrap(), None);
+    assert_eq!(server.metadata().entries, vec![entry]);
+}
+
+#[tokio::test]
+async fn test_anonymous_writes_must_be_allowed() {
+    let root = tempfile::tempdir().unwrap();
+    let storage = PtrStorage::open(root.path()).unwrap();
+    let server = Arc::new(
+        RepositoryServer::open(storage, common::options())
+            .await
+            .unwrap(),
+    );
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    assert!(matches!(
+        Arc::clone(&server).serve(address).await,
+        Err(Error::Builder(_))
+    ));
+
+    let storage = PtrStorage::open(root.path()).unwrap();
+    let server = Arc::new(
+        RepositoryServer::open(storage, common::options())
+            .await
+            .unwrap()
+            .allow_anonymous_writes(true),
+    );
+    let url = serve(&server).await;
+    client(&url, "")
+        .post_update(&ClientToServerUpdate::new().add_mappings("tag", vec![common::file_hash(1)]))
+        .await
+        .unwrap();
+    assert!(server.publish_updates().unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_repository_serves_https() {
+    let root = tempfile::tempdir().unwrap();
+    let server = open_server(root.path()).await;
+    let tls = ServerTls::from_pem(
+        include_bytes!("certs/server.crt"),
+        include_bytes!("certs/server.key"),
+    )
+    .unwrap();
+    let address = free_address();
+    tokio::spawn(Arc::clone(&server).serve_tls(address, tls));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = Client::builder()
+        .endpoint(format!("https://localhost:{}", address.port()))
+        .access_key(ACCESS_KEY)
+        .accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    client
+        .post_update(&ClientToServerUpdate::new().add_mappings("tag", vec![common::file_hash(1)]))
+        .await
+        .unwrap();
+    let entry = server.publish_updates().unwrap().unwrap();
+    let metadata = client.get_metadata(0).await.unwrap();
+    assert_eq!(metadata.0.entries, vec![entry]);
+}
+
+#[tokio::test]
+async fn test_account_is_served() {
+    let root = tempfile::tempdir().unwrap();
+    let server = open_server(root.path()).await;
+    let url = serve(&server).await;
+
+    let account = client(&url, ACCESS_KEY).get_account().await.unwrap();
+    assert_eq!(
+        account.account_key,
+        Sha256Hash::digest(ACCESS_KEY.as_bytes()).to_hex()
+    );
+    assert_eq!(account.account_type, "user");
+    assert_eq!(account.expires, None);
+    assert_eq!(account.bandwidth_rules, BandwidthRules::new());
+
+    let result = client(&url, "invalid").get_account().await;
+    assert!(matches!(result, Err(Error::ResponseStatus(status, _)) if status.as_u16() == 403));
+}
+
+#[tokio::test]
+async fn test_oversized_updates_are_rejected() {
+    let root = tempfile::tempdir().unwrap();
+    let storage = PtrStorage::open(root.path()).unwrap();
+    let server = Arc::new(
+        RepositoryServer::open(storage, common::options())
+            .await
+            .unwrap()
+            .access_key(ACCESS_KEY)
+            .max_update_size(16),
+    );
+    let url = serve(&server).await;
+
+    let result = client(&url, ACCESS_KEY)
+        .post_update(&ClientToServerUpdate::new().add_mappings("tag", vec![common::file_hash(1)]))
+        .await;
+    assert!(matches!(result, Err(Error::ResponseStatus(status, _)) if status.as_u16() == 413));
+    assert_eq!(server.publish_updates().unwrap(), None);
+}
@@ -0,0 +1,117 @@
+use futures_util::StreamExt;
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{Error, MetadataEntry, PtrStorage, Sha256Hash};
+
+mod common;
+
+#[tokio::test]
+async fn test_storage_sync() {
+    let definitions = common::update_file(&common::definitions_update_json());
+    let content = common::update_file(&common::content_update_json());
+    let (definitions_hash, content_hash) = (definitions.hash, content.hash);
+    let server = FakeServer::builder()
+        .options(common::options())
+        .metadata(
+            vec![MetadataEntry {
+                update_index: 0,
+                update_hashes: vec![definitions_hash, content_hash],
+                time_begin: 0,
+                time_end: 100,
+            }],
+            200,
+        )
+        .update_file(definitions)
+        .update_file(content)
+        .build()
+        .await
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let storage = PtrStorage::open(dir.path()).unwrap();
+    assert!(storage.metadata().unwrap().is_none());
+
+    let summary = storage.sync(&server.client()).await.unwrap();
+    assert_eq!(summary.downloaded_updates, 2);
+    assert_eq!(storage.options().unwrap().unwrap().update_period, 100);
+
+    let metadata = storage.metadata().unwrap().unwrap();
+    let updates = storage
+        .update_source()
+        .stream_entries(metadata.0.entries)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(updates.len(), 2);
+    assert!(updates.iter().all(|u| u.is_ok()));
+}
+
+#[tokio::test]
+async fn test_storage_sync_resumes_from_stored_index() {
+    let definitions = common::update_file(&common::definitions_update_json());
+    let content = common::update_file(&common::content_update_json());
+    let first_entry = MetadataEntry {
+        update_index: 0,
+        update_hashes: vec![definitions.hash],
+        time_begin: 0,
+        time_end: 100,
+    };
+    let second_entry = MetadataEntry {
+        update_index: 1,
+        update_hashes: vec![content.hash],
+        time_begin: 100,
+        time_end: 200,
+    };
+    let dir = tempfile::tempdir().unwrap();
+    let storage = PtrStorage::open(dir.path()).unwrap();
+
+    let server = FakeServer::builder()
+        .options(common::options())
+        .metadata(vec![first_entry.clone()], 200)
+        .update_file(definitions)
+        .build()
+        .await
+        .unwrap();
+    storage.sync(&server.client()).await.unwrap();
+
+    // the upstream only returns entries since the requested index
+    let server = FakeServer::builder()
+        .options(common::options())
+        .metadata(vec![second_entry.clone()], 300)
+        .update_file(content)
+        .build()
+        .await
+        .unwrap();
+    let summary = storage.sync(&server.client()).await.unwrap();
+    assert_eq!(summary.downloaded_updates, 1);
+    assert_eq!(summary.total_updates, 2);
+    assert_eq!(server.request_count("update"), 1);
+
+    let metadata = storage.metadata().unwrap().unwrap();
+    assert_eq!(metadata.0.entries, vec![first_entry, second_entry]);
+    assert_eq!(metadata.0.next_update_due, 300);
+}
+
+#[tokio::test]
+async fn test_storage_sync_rejects_mismatching_update() {
+    let hash = Sha256Hash::from_hex(common::DEFINITIONS_UPDATE_HASH).unwrap();
+    let server = FakeServer::builder()
+        .options(common::options())
+        .metadata(
+            vec![MetadataEntry {
+                update_index: 0,
+                update_hashes: vec![hash],
+                time_begin: 0,
+                time_end: 100,
+            }],
+            200,
+        )
+        .update_json(hash, common::definitions_update_json())
+        .build()
+        .await
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let storage = PtrStorage::open(dir.path()).unwrap();
+
+    let result = storage.sync(&server.client()).await;
+    assert!(matches!(result, Err(Error::HashMismatch(expected, _)) if expected == hash));
+    assert!(!storage.has_update(&hash));
+    assert!(storage.metadata().unwrap().is_none());
+}
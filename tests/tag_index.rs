@@ -0,0 +1,137 @@
+use hydrus_ptr_client::{
+    ContentUpdateAction, ContentUpdateResponse, DefinitionsUpdateResponse, TagDisplay, TagIndex,
+    UpdateResponse,
+};
+use std::collections::HashMap;
+
+mod common;
+
+fn definitions() -> UpdateResponse {
+    UpdateResponse::Definitions(DefinitionsUpdateResponse {
+        hashes: HashMap::from([(1, common::file_hash(1)), (2, common::file_hash(2))]),
+        tags: HashMap::from([
+            (1, String::from("character:samus aran")),
+            (2, String::from("series:metroid")),
+            (3, String::from("samus")),
+            (4, String::from("nintendo")),
+        ]),
+    })
+}
+
+fn content(
+    action: ContentUpdateAction,
+    mappings: Vec<(u64, Vec<u64>)>,
+    tag_siblings: Vec<(u64, u64)>,
+    tag_parents: Vec<(u64, u64)>,
+) -> UpdateResponse {
+    UpdateResponse::Content(ContentUpdateResponse {
+        mappings: HashMap::from([(action.clone(), mappings.into_iter().collect())]),
+        tag_siblings: HashMap::from([(action.clone(), tag_siblings.into_iter().collect())]),
+        tag_parents: HashMap::from([(action, tag_parents.into_iter().collect())]),
+    })
+}
+
+fn index() -> TagIndex {
+    let mut index = TagIndex::new();
+    index.apply(&definitions());
+    index.apply(&content(
+        ContentUpdateAction::Add,
+        vec![(3, vec![1]), (1, vec![2]), (4, vec![1, 2])],
+        vec![(3, 1)],
+        vec![(1, 2)],
+    ));
+
+    index
+}
+
+#[test]
+fn test_storage_lookup() {
+    let index = index();
+
+    assert_eq!(
+        index.tags_for_hash(&common::file_hash(1), TagDisplay::Storage),
+        vec!["nintendo", "samus"]
+    );
+    assert_eq!(
+        index.hashes_for_tag("samus", TagDisplay::Storage),
+        vec![&common::file_hash(1)]
+    );
+    assert_eq!(index.count_for_tag("nintendo", TagDisplay::Storage), 2);
+    assert_eq!(
+        index.count_for_tag("series:metroid", TagDisplay::Storage),
+        0
+    );
+    assert_eq!(index.count_for_tag("unknown", TagDisplay::Storage), 0);
+    assert!(index
+        .tags_for_hash(&common::file_hash(3), TagDisplay::Storage)
+        .is_empty());
+}
+
+#[test]
+fn test_display_lookup() {
+    let index = index();
+
+    assert_eq!(index.ideal_sibling("samus"), Some("character:samus aran"));
+    assert_eq!(
+        index.tags_for_hash(&common::file_hash(1), TagDisplay::Siblings),
+        vec!["character:samus aran", "nintendo"]
+    );
+    assert_eq!(
+        index.tags_for_hash(&common::file_hash(1), TagDisplay::SiblingsAndParents),
+        vec!["character:samus aran", "nintendo", "series:metroid"]
+    );
+    assert_eq!(index.count_for_tag("samus", TagDisplay::Siblings), 2);
+    assert_eq!(
+        index.count_for_tag("series:metroid", TagDisplay::SiblingsAndParents),
+        2
+    );
+}
+
+#[test]
+fn test_deletions() {
+    let mut index = index();
+    index.apply(&content(
+        ContentUpdateAction::Delete,
+        vec![(4, vec![1])],
+        vec![(3, 1)],
+        vec![(1, 2)],
+    ));
+
+    assert_eq!(index.count_for_tag("nintendo", TagDisplay::Storage), 1);
+    assert_eq!(index.ideal_sibling("samus"), Some("samus"));
+    assert_eq!(
+        index.tags_for_hash(&common::file_hash(1), TagDisplay::SiblingsAndParents),
+        vec!["samus"]
+    );
+    assert_eq!(
+        index.count_for_tag("series:metroid", TagDisplay::SiblingsAndParents),
+        0
+    );
+}
+
+#[test]
+fn test_replaced_sibling() {
+    let mut index = index();
+    // samus now resolves to nintendo instead of samus aran
+    index.apply(&content(
+        ContentUpdateAction::Add,
+        Vec::new(),
+        vec![(3, 4)],
+        Vec::new(),
+    ));
+
+    assert_eq!(index.ideal_sibling("samus"), Some("nintendo"));
+    assert_eq!(
+        index.count_for_tag("character:samus aran", TagDisplay::Siblings),
+        1
+    );
+    assert_eq!(index.count_for_tag("nintendo", TagDisplay::Siblings), 2);
+    assert_eq!(
+        index.count_for_tag("series:metroid", TagDisplay::SiblingsAndParents),
+        1
+    );
+    assert_eq!(
+        index.tags_for_hash(&common::file_hash(1), TagDisplay::SiblingsAndParents),
+        vec!["nintendo"]
+    );
+}
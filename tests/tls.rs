@@ -0,0 +1,244 @@
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::Client;
+
+mod common;
+
+#[tokio::test]
+async fn test_requests_are_sent_through_proxy() {
+    let server = FakeServer::builder()
+        .metadata(Vec::new(), 100)
+        .build()
+        .await
+        .unwrap();
+    let client = Client::builder()
+        .endpoint("http://ptr.invalid")
+        .proxy(reqwest::Proxy::http(server.url()).unwrap())
+        .build()
+        .unwrap();
+
+    let metadata = client.get_metadata(0).await.unwrap();
+    assert_eq!(metadata.0.next_update_due, 100);
+    assert_eq!(server.request_count("metadata"), 1);
+}
+
+#[cfg(feature = "server")]
+mod root_certificate {
+    use super::common;
+    use hydrus_ptr_client::{Client, PtrStorage, RepositoryServer, ServerTls};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    /// Serves a repository with a certificate for localhost signed by the test CA
+    async fn serve_tls() -> (u16, TempDir) {
+        let root = tempfile::tempdir().unwrap();
+        let storage = PtrStorage::open(root.path()).unwrap();
+        let server = RepositoryServer::open(storage, common::options())
+            .await
+            .unwrap()
+            .access_key("key");
+        let tls = ServerTls::from_pem(
+            include_bytes!("certs/localhost.crt"),
+            include_bytes!("certs/localhost.key"),
+        )
+        .unwrap();
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        tokio::spawn(Arc::new(server).serve_tls(address, tls));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        (address.port(), root)
+    }
+
+    fn client(port: u16, root_certificate: Option<reqwest::Certificate>) -> Client {
+        let mut builder = Client::builder().endpoint(format!("https://localhost:{}", port));
+        if let Some(certificate) = root_certificate {
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        builder.build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_root_certificate_is_trusted() {
+        let (port, _root) = serve_tls().await;
+        let certificate = reqwest::Certificate::from_pem(include_bytes!("certs/ca.crt")).unwrap();
+
+        let options = client(port, Some(certificate)).get_options().await.unwrap();
+        assert_eq!(options.server_message, "hello");
+    }
+
+    #[tokio::test]
+    async fn test_certificate_without_root_is_rejected() {
+        let (port, _root) = serve_tls().await;
+
+        assert!(client(port, None).get_options().await.is_err());
+    }
+}
+
+#[cfg(feature = "rustls")]
+mod rustls_tls {
+    use super::common;
+    use hydrus_ptr_client::test_util::{Cassette, ReplayTransport};
+    use hydrus_ptr_client::{Client, Error, HydrusMetadata, MetadataResponse, Sha256Hash, ToJson};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    const CERTIFICATE: &[u8] = include_bytes!("certs/server.crt.der");
+    const KEY: &[u8] = include_bytes!("certs/server.key.der");
+
+    /// Starts a tls server with the self-signed certificate
+    fn start_tls_server() -> u16 {
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![rustls::Certificate(CERTIFICATE.to_vec())],
+                rustls::PrivateKey(KEY.to_vec()),
+            )
+            .unwrap();
+
+        serve(config)
+    }
+
+    /// Starts a tls server for localhost that requires a client certificate signed by the test CA
+    fn start_mutual_tls_server() -> u16 {
+        let mut roots = rustls::RootCertStore::empty();
+        roots
+            .add(&rustls::Certificate(
+                include_bytes!("certs/ca.crt.der").to_vec(),
+            ))
+            .unwrap();
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(
+                rustls::server::AllowAnyAuthenticatedClient::new(roots).boxed(),
+            )
+            .with_single_cert(
+                vec![rustls::Certificate(
+                    include_bytes!("certs/localhost.crt.der").to_vec(),
+                )],
+                rustls::PrivateKey(include_bytes!("certs/localhost.key.der").to_vec()),
+            )
+            .unwrap();
+
+        serve(config)
+    }
+
+    /// Answers every request with empty metadata
+    fn serve(config: rustls::ServerConfig) -> u16 {
+        let config = Arc::new(config);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let body = common::compress_json(
+            &MetadataResponse(HydrusMetadata {
+                entries: Vec::new(),
+                next_update_due: 100,
+            })
+            .to_json()
+            .unwrap(),
+        );
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let connection = rustls::ServerConnection::new(Arc::clone(&config)).unwrap();
+                let mut stream = rustls::StreamOwned::new(connection, stream);
+                let mut reader = BufReader::new(&mut stream);
+                let mut line = String::new();
+                while reader.read_line(&mut line).map(|n| n > 2).unwrap_or(false) {
+                    line.clear();
+                }
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(header.as_bytes());
+                let _ = stream.write_all(&body);
+                let _ = stream.flush();
+            }
+        });
+
+        port
+    }
+
+    fn client(port: u16, fingerprint: Sha256Hash) -> Client {
+        Client::builder()
+            .endpoint(format!("https://localhost:{}", port))
+            .pinned_certificate(fingerprint)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pinned_certificate_is_accepted() {
+        let port = start_tls_server();
+        let client = client(port, Sha256Hash::digest(CERTIFICATE));
+
+        let metadata = client.get_metadata(0).await.unwrap();
+        assert_eq!(metadata.0.next_update_due, 100);
+    }
+
+    #[test]
+    fn test_pinning_excludes_other_certificate_options() {
+        let certificate =
+            reqwest::Certificate::from_der(include_bytes!("certs/server.crt.der")).unwrap();
+        let pinned = || Client::builder().pinned_certificate(Sha256Hash::digest(CERTIFICATE));
+
+        assert!(matches!(
+            pinned().add_root_certificate(certificate).build(),
+            Err(Error::Builder(_))
+        ));
+        assert!(matches!(
+            pinned().accept_invalid_certs(true).build(),
+            Err(Error::Builder(_))
+        ));
+        assert!(pinned().accept_invalid_certs(false).build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unpinned_certificate_is_rejected() {
+        let port = start_tls_server();
+        let client = client(port, Sha256Hash::new([0; 32]));
+
+        assert!(client.get_metadata(0).await.is_err());
+    }
+
+    fn mutual_tls_client(port: u16, identity: bool) -> Client {
+        let certificate = reqwest::Certificate::from_pem(include_bytes!("certs/ca.crt")).unwrap();
+        let mut builder = Client::builder()
+            .endpoint(format!("https://localhost:{}", port))
+            .add_root_certificate(certificate);
+        if identity {
+            let identity = reqwest::Identity::from_pem(include_bytes!("certs/client.pem")).unwrap();
+            builder = builder.identity(identity);
+        }
+
+        builder.build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_identity_is_presented() {
+        let port = start_mutual_tls_server();
+
+        let metadata = mutual_tls_client(port, true).get_metadata(0).await.unwrap();
+        assert_eq!(metadata.0.next_update_due, 100);
+        assert!(mutual_tls_client(port, false)
+            .get_metadata(0)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_pinning_excludes_custom_transports() {
+        let result = Client::builder()
+            .pinned_certificate(Sha256Hash::digest(CERTIFICATE))
+            .transport(ReplayTransport::new(Cassette::default()))
+            .build();
+
+        assert!(matches!(result, Err(Error::Builder(_))));
+    }
+}
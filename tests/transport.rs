@@ -0,0 +1,216 @@
+use hydrus_ptr_client::{
+    Client, ClientToServerUpdate, Error, HttpMethod, HttpRequest, HttpResponse, HttpTransport,
+    HydrusMetadata, MetadataResponse, RetryPolicy, Sha256Hash, ToJson, TransportFuture,
+};
+use reqwest::StatusCode;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+mod common;
+
+/// Answers requests with queued responses and records them
+#[derive(Clone, Default)]
+struct MemoryTransport {
+    responses: Arc<Mutex<VecDeque<HttpResponse>>>,
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl MemoryTransport {
+    fn respond(&self, status: u16, body: Vec<u8>) {
+        self.respond_with_headers(status, Vec::new(), body);
+    }
+
+    fn respond_with_headers(&self, status: u16, headers: Vec<(String, String)>, body: Vec<u8>) {
+        self.responses.lock().unwrap().push_back(HttpResponse {
+            status: StatusCode::from_u16(status).unwrap(),
+            headers,
+            body,
+        });
+    }
+
+    fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl HttpTransport for MemoryTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        self.requests.lock().unwrap().push(request);
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("no response queued");
+
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+fn client(transport: &MemoryTransport) -> Client {
+    Client::builder()
+        .endpoint("http://ptr.invalid")
+        .access_key("key")
+        .transport(transport.clone())
+        .retry_policy(RetryPolicy::new(2).base_delay(std::time::Duration::ZERO))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_requests_go_through_transport() {
+    let transport = MemoryTransport::default();
+    let metadata = MetadataResponse(HydrusMetadata {
+        entries: Vec::new(),
+        next_update_due: 100,
+    });
+    transport.respond(200, common::compress_json(&metadata.to_json().unwrap()));
+    let client = client(&transport);
+
+    let metadata = client.get_metadata(5).await.unwrap();
+    assert_eq!(metadata.0.next_update_due, 100);
+
+    let requests = transport.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, HttpMethod::Get);
+    assert_eq!(requests[0].url, "http://ptr.invalid/metadata?since=5");
+    assert_eq!(requests[0].header("hydrus-key"), Some("key"));
+    assert_eq!(requests[0].body, None);
+}
+
+#[tokio::test]
+async fn test_error_responses_are_retried() {
+    let transport = MemoryTransport::default();
+    transport.respond(503, b"busy".to_vec());
+    transport.respond(404, b"not found".to_vec());
+    let client = client(&transport);
+
+    let result = client.get_options().await;
+    assert!(
+        matches!(result, Err(Error::ResponseStatus(status, message)) if status == StatusCode::NOT_FOUND && message == "not found")
+    );
+    assert_eq!(transport.requests().len(), 2);
+    assert_eq!(transport.requests()[1].url, "http://ptr.invalid/options");
+}
+
+#[tokio::test]
+async fn test_post_requests_are_not_retried() {
+    let transport = MemoryTransport::default();
+    transport.respond(503, b"busy".to_vec());
+    let client = client(&transport);
+
+    let result = client
+        .post_update(
+            &ClientToServerUpdate::new().add_mappings("tag", vec![Sha256Hash::digest(b"file")]),
+        )
+        .await;
+    assert!(
+        matches!(result, Err(Error::ResponseStatus(status, _)) if status == StatusCode::SERVICE_UNAVAILABLE)
+    );
+    assert_eq!(transport.requests().len(), 1);
+    assert_eq!(transport.requests()[0].method, HttpMethod::Post);
+}
+
+#[tokio::test]
+async fn test_exceeded_bandwidth_waits_until_reset() {
+    let transport = MemoryTransport::default();
+    let metadata = MetadataResponse(HydrusMetadata {
+        entries: Vec::new(),
+        next_update_due: 100,
+    });
+    transport.respond_with_headers(
+        509,
+        vec![(String::from("Retry-After"), String::from("1"))],
+        b"bandwidth exceeded".to_vec(),
+    );
+    transport.respond(200, common::compress_json(&metadata.to_json().unwrap()));
+    let client = client(&transport);
+
+    let start = std::time::Instant::now();
+    let metadata = client.get_metadata(0).await.unwrap();
+    assert_eq!(metadata.0.next_update_due, 100);
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(transport.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_exceeded_bandwidth_without_reset_is_not_retried() {
+    let transport = MemoryTransport::default();
+    transport.respond(509, b"bandwidth exceeded".to_vec());
+    let client = client(&transport);
+
+    let result = client.get_options().await;
+    assert!(matches!(
+        result,
+        Err(Error::RepositoryBandwidthExceeded(message, None)) if message == "bandwidth exceeded"
+    ));
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_exceeded_bandwidth_with_distant_reset_is_not_retried() {
+    let transport = MemoryTransport::default();
+    transport.respond_with_headers(
+        509,
+        vec![(String::from("Retry-After"), String::from("86400"))],
+        b"bandwidth exceeded".to_vec(),
+    );
+    let client = client(&transport);
+
+    let result = client.get_options().await;
+    assert!(matches!(
+        result,
+        Err(Error::RepositoryBandwidthExceeded(_, Some(reset_in))) if reset_in == Duration::from_secs(86400)
+    ));
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[test]
+fn test_retry_delay() {
+    let policy = RetryPolicy::new(2)
+        .base_delay(Duration::from_millis(100))
+        .jitter(false);
+    let server_error = Error::ResponseStatus(StatusCode::BAD_GATEWAY, String::new());
+    let not_found = Error::ResponseStatus(StatusCode::NOT_FOUND, String::new());
+    let bandwidth =
+        Error::RepositoryBandwidthExceeded(String::new(), Some(Duration::from_secs(3600)));
+
+    assert_eq!(
+        policy.retry_delay(1, &server_error),
+        Some(Duration::from_millis(100))
+    );
+    assert_eq!(policy.retry_delay(2, &server_error), None);
+    assert_eq!(policy.retry_delay(1, &not_found), None);
+    assert_eq!(policy.retry_delay(1, &bandwidth), None);
+
+    let policy = policy.max_delay(Duration::from_secs(7200));
+    assert_eq!(
+        policy.retry_delay(1, &bandwidth),
+        Some(Duration::from_secs(3600))
+    );
+    assert_eq!(policy.retry_delay(2, &bandwidth), None);
+    assert!(!Error::Malformed.is_transient());
+}
+
+#[test]
+fn test_reqwest_options_exclude_custom_transports() {
+    let transport = MemoryTransport::default();
+    let builder = || Client::builder().transport(transport.clone());
+
+    assert!(matches!(
+        builder().timeout(Duration::from_secs(1)).build(),
+        Err(Error::Builder(_))
+    ));
+    assert!(matches!(
+        builder()
+            .proxy(reqwest::Proxy::all("http://127.0.0.1:1").unwrap())
+            .build(),
+        Err(Error::Builder(_))
+    ));
+    assert!(matches!(
+        builder().accept_invalid_certs(true).build(),
+        Err(Error::Builder(_))
+    ));
+    assert!(builder().accept_invalid_certs(false).build().is_ok());
+}
@@ -0,0 +1,112 @@
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{Client, Error, Sha256Hash, UpdateResponse};
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+mod common;
+
+const UNREACHABLE_ENDPOINT: &str = "http://127.0.0.1:9";
+const FIRST_HASH: &str = "4a4d13c1fcdf0cf734927ec4c9637fdac6144512ad7dc919e0f222e7b0e71586";
+const SECOND_HASH: &str = "cd1418ffeba0b8fe46aefa51a7adf1210356523ead658b182762ff61b73ebae5";
+const THIRD_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+
+fn write_cached_file(dir: &std::path::Path, hash: &str, bytes: &[u8], age_secs: u64) {
+    let path = dir.join(hash);
+    fs::write(&path, bytes).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_cache_hit_without_network() {
+    let dir = tempfile::tempdir().unwrap();
+    let bytes = common::compress_json(&common::definitions_update_json());
+    let hash = Sha256Hash::digest(&bytes);
+    write_cached_file(dir.path(), &hash.to_hex(), &bytes, 0);
+
+    let client = Client::builder()
+        .endpoint(UNREACHABLE_ENDPOINT)
+        .update_cache(dir.path(), 1024 * 1024)
+        .build()
+        .unwrap();
+    let update = client.get_update(&hash).await.unwrap();
+    assert!(matches!(update, UpdateResponse::Definitions(_)));
+
+    let missing = client
+        .get_update(&Sha256Hash::from_hex(SECOND_HASH).unwrap())
+        .await;
+    assert!(missing.is_err());
+}
+
+#[tokio::test]
+async fn test_cache_evicts_least_recently_used() {
+    let dir = tempfile::tempdir().unwrap();
+    let bytes = common::compress_json(&common::definitions_update_json());
+    write_cached_file(dir.path(), FIRST_HASH, &bytes, 300);
+    write_cached_file(dir.path(), SECOND_HASH, &bytes, 200);
+    write_cached_file(dir.path(), THIRD_HASH, &bytes, 100);
+
+    Client::builder()
+        .update_cache(dir.path(), bytes.len() as u64 * 2)
+        .build()
+        .unwrap();
+
+    assert!(!dir.path().join(FIRST_HASH).exists());
+    assert!(dir.path().join(SECOND_HASH).exists());
+    assert!(dir.path().join(THIRD_HASH).exists());
+}
+
+#[tokio::test]
+async fn test_corrupt_cached_file_is_evicted() {
+    let dir = tempfile::tempdir().unwrap();
+    let bytes = common::compress_json(&common::definitions_update_json());
+    let hash = Sha256Hash::digest(&bytes);
+    write_cached_file(dir.path(), &hash.to_hex(), &bytes[..bytes.len() / 2], 0);
+
+    let client = Client::builder()
+        .endpoint(UNREACHABLE_ENDPOINT)
+        .update_cache(dir.path(), 1024 * 1024)
+        .build()
+        .unwrap();
+    assert!(client.get_update(&hash).await.is_err());
+    assert!(!dir.path().join(hash.to_hex()).exists());
+}
+
+#[tokio::test]
+async fn test_mismatching_download_is_not_cached() {
+    let hash = Sha256Hash::from_hex(FIRST_HASH).unwrap();
+    let server = FakeServer::builder()
+        .update_json(hash, common::definitions_update_json())
+        .build()
+        .await
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let client = Client::builder()
+        .endpoint(server.url())
+        .update_cache(dir.path(), 1024 * 1024)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        client.get_update(&hash).await,
+        Err(Error::HashMismatch(expected, _)) if expected == hash
+    ));
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_interrupted_writes_are_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let tmp_path = dir.path().join(format!("{}.1.0.tmp", FIRST_HASH));
+    fs::write(&tmp_path, b"partial").unwrap();
+
+    Client::builder()
+        .update_cache(dir.path(), 1024 * 1024)
+        .build()
+        .unwrap();
+    assert!(!tmp_path.exists());
+}
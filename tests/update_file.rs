@@ -0,0 +1,100 @@
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{
+    ContentUpdateAction, ContentUpdateBuilder, DefinitionsUpdateBuilder, Sha256Hash, UpdateResponse,
+};
+
+mod common;
+
+#[tokio::test]
+async fn test_built_update_files_are_readable() {
+    let definitions = DefinitionsUpdateBuilder::new()
+        .hash(1, common::file_hash(1))
+        .hash(2, common::file_hash(2))
+        .tag(1, "character:samus aran")
+        .tag(2, "series:metroid")
+        .build()
+        .unwrap();
+    let content = ContentUpdateBuilder::new()
+        .mappings(ContentUpdateAction::Add, 1, vec![1, 2])
+        .mappings(ContentUpdateAction::Delete, 2, vec![2])
+        .tag_sibling(ContentUpdateAction::Add, 2, 1)
+        .tag_parent(ContentUpdateAction::Delete, 1, 2)
+        .build()
+        .unwrap();
+    assert_eq!(definitions.hash, Sha256Hash::digest(&definitions.bytes));
+    assert_eq!(content.hash, Sha256Hash::digest(&content.bytes));
+
+    let server = FakeServer::builder()
+        .update_file(definitions.clone())
+        .update_file(content.clone())
+        .build()
+        .await
+        .unwrap();
+    let client = server.client();
+
+    let definitions = match client.get_update(&definitions.hash).await.unwrap() {
+        UpdateResponse::Definitions(definitions) => definitions,
+        other => panic!("expected definitions update, got {:?}", other),
+    };
+    assert_eq!(definitions.hashes[&2], common::file_hash(2));
+    assert_eq!(definitions.tags[&1], "character:samus aran");
+
+    let content = match client.get_update(&content.hash).await.unwrap() {
+        UpdateResponse::Content(content) => content,
+        other => panic!("expected content update, got {:?}", other),
+    };
+    assert_eq!(content.mappings[&ContentUpdateAction::Add][&1], vec![1, 2]);
+    assert_eq!(content.mappings[&ContentUpdateAction::Delete][&2], vec![2]);
+    assert_eq!(content.tag_siblings[&ContentUpdateAction::Add][&2], 1);
+    assert_eq!(content.tag_parents[&ContentUpdateAction::Delete][&1], 2);
+}
+
+#[tokio::test]
+async fn test_update_files_are_rebuilt_from_responses() {
+    let original = ContentUpdateBuilder::new()
+        .mappings(ContentUpdateAction::Add, 3, vec![5, 4])
+        .tag_sibling(ContentUpdateAction::Add, 1, 2)
+        .build()
+        .unwrap();
+    let server = FakeServer::builder()
+        .update_file(original.clone())
+        .build()
+        .await
+        .unwrap();
+
+    let content = match server.client().get_update(&original.hash).await.unwrap() {
+        UpdateResponse::Content(content) => content,
+        other => panic!("expected content update, got {:?}", other),
+    };
+    let rebuilt = ContentUpdateBuilder::from(content).build().unwrap();
+
+    assert_eq!(rebuilt, original);
+}
+
+#[test]
+fn test_empty_builders() {
+    assert!(DefinitionsUpdateBuilder::new().is_empty());
+    assert!(ContentUpdateBuilder::new()
+        .mappings(ContentUpdateAction::Add, 1, Vec::new())
+        .is_empty());
+    assert!(!DefinitionsUpdateBuilder::new().tag(1, "tag").is_empty());
+}
+
+#[test]
+fn test_opposite_changes_cancel() {
+    let cancelled = ContentUpdateBuilder::new()
+        .mappings(ContentUpdateAction::Add, 1, vec![1, 2])
+        .mappings(ContentUpdateAction::Delete, 1, vec![1])
+        .tag_sibling(ContentUpdateAction::Add, 2, 1)
+        .tag_sibling(ContentUpdateAction::Delete, 2, 1)
+        .build()
+        .unwrap();
+    let expected = ContentUpdateBuilder::new()
+        .mappings(ContentUpdateAction::Add, 1, vec![2])
+        .mappings(ContentUpdateAction::Delete, 1, vec![1])
+        .tag_sibling(ContentUpdateAction::Delete, 2, 1)
+        .build()
+        .unwrap();
+
+    assert_eq!(cancelled, expected);
+}
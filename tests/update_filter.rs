@@ -0,0 +1,209 @@
+use futures_util::StreamExt;
+use hydrus_ptr_client::test_util::FakeServer;
+use hydrus_ptr_client::{
+    ContentUpdateAction, ContentUpdateBuilder, DefinitionsUpdateBuilder, DefinitionsUpdateResponse,
+    Error, MetadataEntry, Sha256Hash, StreamOrdering, UpdateFile, UpdateFilter, UpdateResponse,
+};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
+mod common;
+
+/// Returns a definitions and a content update file
+fn update_files() -> (UpdateFile, UpdateFile) {
+    let definitions = DefinitionsUpdateBuilder::new()
+        .hash(1, common::file_hash(1))
+        .hash(2, common::file_hash(2))
+        .tag(1, "character:samus aran")
+        .tag(2, "series:metroid")
+        .tag(3, "samus")
+        .build()
+        .unwrap();
+    let content = ContentUpdateBuilder::new()
+        .mappings(ContentUpdateAction::Add, 1, vec![1, 2])
+        .mappings(ContentUpdateAction::Add, 2, vec![2])
+        .mappings(ContentUpdateAction::Delete, 3, vec![1])
+        .tag_sibling(ContentUpdateAction::Add, 3, 1)
+        .tag_parent(ContentUpdateAction::Add, 1, 2)
+        .build()
+        .unwrap();
+
+    (definitions, content)
+}
+
+async fn build_server() -> FakeServer {
+    let (definitions, content) = update_files();
+
+    FakeServer::builder()
+        .metadata(
+            vec![MetadataEntry {
+                update_index: 0,
+                // content is listed first to check that it's filtered after the definitions
+                update_hashes: vec![content.hash, definitions.hash],
+                time_begin: 0,
+                time_end: 100,
+            }],
+            200,
+        )
+        .update_file(definitions)
+        .update_file(content)
+        .build()
+        .await
+        .unwrap()
+}
+
+async fn filtered_updates(filter: UpdateFilter) -> Vec<UpdateResponse> {
+    let server = build_server().await;
+    let stream = server
+        .client()
+        .stream_updates(0)
+        .await
+        .unwrap()
+        .ordering(StreamOrdering::DefinitionsFirst)
+        .update_filter(filter);
+
+    stream.map(|u| u.unwrap().update).collect().await
+}
+
+#[tokio::test]
+async fn test_filter_by_hashes() {
+    let updates =
+        filtered_updates(UpdateFilter::new().hashes(HashSet::from([common::file_hash(1)]))).await;
+    assert_eq!(updates.len(), 2);
+
+    let UpdateResponse::Definitions(definitions) = &updates[0] else {
+        panic!("expected definitions update first");
+    };
+    assert_eq!(definitions.hashes.keys().collect::<Vec<_>>(), vec![&1]);
+    assert_eq!(definitions.tags.len(), 3);
+
+    let UpdateResponse::Content(content) = &updates[1] else {
+        panic!("expected content update second");
+    };
+    let added = &content.mappings[&ContentUpdateAction::Add];
+    assert_eq!(added.len(), 1);
+    assert_eq!(added[&1], vec![1]);
+    assert_eq!(content.mappings[&ContentUpdateAction::Delete][&3], vec![1]);
+    assert_eq!(content.tag_siblings[&ContentUpdateAction::Add][&3], 1);
+    assert_eq!(content.tag_parents[&ContentUpdateAction::Add][&1], 2);
+}
+
+#[tokio::test]
+async fn test_filter_by_namespaces() {
+    let updates = filtered_updates(UpdateFilter::new().namespaces(["character", "series"])).await;
+
+    let UpdateResponse::Definitions(definitions) = &updates[0] else {
+        panic!("expected definitions update first");
+    };
+    assert_eq!(definitions.hashes.len(), 2);
+    assert!(!definitions.tags.contains_key(&3));
+
+    let UpdateResponse::Content(content) = &updates[1] else {
+        panic!("expected content update second");
+    };
+    assert_eq!(content.mappings[&ContentUpdateAction::Add].len(), 2);
+    assert!(!content.mappings.contains_key(&ContentUpdateAction::Delete));
+    assert!(content.tag_siblings.is_empty());
+    assert_eq!(content.tag_parents[&ContentUpdateAction::Add][&1], 2);
+}
+
+#[tokio::test]
+async fn test_filter_keeps_known_ids() {
+    let filter = UpdateFilter::new()
+        .hashes(|_: &Sha256Hash| false)
+        .known_ids([2], []);
+    let updates = filtered_updates(filter).await;
+
+    let UpdateResponse::Content(content) = &updates[1] else {
+        panic!("expected content update second");
+    };
+    let added = &content.mappings[&ContentUpdateAction::Add];
+    assert_eq!(added[&1], vec![2]);
+    assert_eq!(added[&2], vec![2]);
+}
+
+#[test]
+fn test_allows_tag() {
+    let filter = UpdateFilter::new().namespaces(["", "creator"]);
+
+    assert!(filter.allows_tag("samus"));
+    assert!(filter.allows_tag("creator:nintendo"));
+    assert!(!filter.allows_tag("series:metroid"));
+    assert!(UpdateFilter::new().allows_tag("series:metroid"));
+}
+
+#[test]
+fn test_only_filtered_ids_are_remembered() {
+    let mut definitions = DefinitionsUpdateResponse {
+        hashes: HashMap::from([(1, common::file_hash(1)), (2, common::file_hash(2))]),
+        tags: HashMap::from([
+            (1, String::from("character:samus aran")),
+            (2, String::from("samus")),
+        ]),
+    };
+    let mut filter = UpdateFilter::new().namespaces(["character"]);
+    filter.apply_definitions(&mut definitions);
+
+    assert_eq!(definitions.hashes.len(), 2);
+    assert!(filter.kept_hash_ids().is_empty());
+    assert_eq!(filter.kept_tag_ids(), &HashSet::from([1]));
+}
+
+#[test]
+fn test_decoding_matches_filtering_decoded_updates() {
+    let (definitions, content) = update_files();
+    let filter = || {
+        UpdateFilter::new()
+            .hashes(HashSet::from([common::file_hash(2)]))
+            .namespaces(["character", ""])
+    };
+    let mut decoding_filter = filter();
+    let mut applied_filter = filter();
+
+    for file in [definitions, content] {
+        let decoded = decoding_filter.decode_update(&file.bytes).unwrap();
+        let mut applied = UpdateFilter::new().decode_update(&file.bytes).unwrap();
+        applied_filter.apply(&mut applied);
+
+        assert_eq!(
+            serde_json::to_value(decoded).unwrap(),
+            serde_json::to_value(applied).unwrap()
+        );
+    }
+    assert_eq!(decoding_filter.kept_hash_ids(), &HashSet::from([2]));
+    assert_eq!(decoding_filter.kept_tag_ids(), &HashSet::from([1, 3]));
+}
+
+#[test]
+fn test_decoding_skips_unknown_content() {
+    let bytes = common::compress_json(&json!([
+        34,
+        1,
+        [
+            [0, [[0, [[1, [1]]]], [7, [[2, [1]]]]]],
+            [9, [[0, [[1, 2]]]]]
+        ]
+    ]));
+    let update = UpdateFilter::new().decode_update(&bytes).unwrap();
+
+    let UpdateResponse::Content(content) = update else {
+        panic!("expected content update");
+    };
+    assert_eq!(content.mappings.len(), 1);
+    assert_eq!(content.mappings[&ContentUpdateAction::Add][&1], vec![1]);
+    assert!(content.tag_siblings.is_empty());
+    assert!(content.tag_parents.is_empty());
+
+    let options = common::compress_json(&json!([21, 1, []]));
+    assert!(matches!(
+        UpdateFilter::new().decode_update(&options),
+        Err(Error::Malformed)
+    ));
+}
+
+#[test]
+fn test_filter_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    assert_send_sync(&UpdateFilter::new().hashes(HashSet::from([common::file_hash(1)])));
+}
@@ -0,0 +1,113 @@
+use futures_util::StreamExt;
+use hydrus_ptr_client::{
+    FileSystemUpdateSource, MetadataEntry, Sha256Hash, UpdateResponse, UpdateSource,
+};
+use std::fs;
+
+mod common;
+
+use common::{CONTENT_UPDATE_HASH, DEFINITIONS_UPDATE_HASH};
+
+fn setup_update_dir() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join(DEFINITIONS_UPDATE_HASH),
+        common::compress_json(&common::definitions_update_json()),
+    )
+    .unwrap();
+    let nested_dir = dir.path().join(format!("f{}", &CONTENT_UPDATE_HASH[..2]));
+    fs::create_dir(&nested_dir).unwrap();
+    fs::write(
+        nested_dir.join(CONTENT_UPDATE_HASH),
+        common::compress_json(&common::content_update_json()),
+    )
+    .unwrap();
+
+    dir
+}
+
+#[tokio::test]
+async fn test_filesystem_get_update() {
+    let dir = setup_update_dir();
+    let source = FileSystemUpdateSource::new(dir.path());
+
+    let definitions_hash = Sha256Hash::from_hex(DEFINITIONS_UPDATE_HASH).unwrap();
+    match source.get_update(&definitions_hash).await.unwrap() {
+        UpdateResponse::Definitions(definitions) => {
+            assert_eq!(
+                definitions.hashes.get(&1).unwrap().to_hex(),
+                common::FILE_HASH
+            );
+            assert_eq!(definitions.tags.get(&1).unwrap(), "character:samus aran");
+        }
+        UpdateResponse::Content(_) => panic!("expected a definitions update"),
+    }
+
+    let content_hash = Sha256Hash::from_hex(CONTENT_UPDATE_HASH).unwrap();
+    assert!(matches!(
+        source.get_update(&content_hash).await.unwrap(),
+        UpdateResponse::Content(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_filesystem_missing_update() {
+    let dir = setup_update_dir();
+    let source = FileSystemUpdateSource::new(dir.path());
+    let missing_hash = Sha256Hash::new([0u8; 32]);
+
+    assert!(source.get_update(&missing_hash).await.is_err());
+}
+
+#[tokio::test]
+async fn test_filesystem_update_stream() {
+    let dir = setup_update_dir();
+    let source = FileSystemUpdateSource::new(dir.path());
+    let mut hashes = source.update_hashes().unwrap();
+    hashes.sort();
+    assert_eq!(hashes.len(), 2);
+
+    let updates = source
+        .stream_updates(hashes.clone())
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].hash, hashes[0]);
+    assert_eq!(updates[1].hash, hashes[1]);
+}
+
+#[tokio::test]
+async fn test_filesystem_entries_stream_in_update_order() {
+    let dir = setup_update_dir();
+    let source = FileSystemUpdateSource::new(dir.path());
+    let definitions_hash = Sha256Hash::from_hex(DEFINITIONS_UPDATE_HASH).unwrap();
+    let content_hash = Sha256Hash::from_hex(CONTENT_UPDATE_HASH).unwrap();
+    let entries = vec![
+        MetadataEntry {
+            update_index: 0,
+            update_hashes: vec![definitions_hash],
+            time_begin: 0,
+            time_end: 100,
+        },
+        MetadataEntry {
+            update_index: 1,
+            update_hashes: vec![content_hash],
+            time_begin: 100,
+            time_end: 200,
+        },
+    ];
+
+    let updates = source
+        .stream_entries(entries)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(updates[0].hash, definitions_hash);
+    assert_eq!(updates[1].hash, content_hash);
+    assert_eq!(updates[1].update_index, 1);
+}